@@ -0,0 +1,250 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use flate2::read::GzDecoder;
+use tar::Archive;
+
+use crate::decoder::is_metrics_file;
+use crate::error::Error;
+
+/// Called with the host, the file name and the content of every metrics file in an archive.
+pub(crate) type MetricsFileVisitor<'a> = dyn FnMut(&str, &str, &[u8]) -> Result<(), Error> + 'a;
+
+/// Called with the host, the path below the directory of the host, the size and a reader of the
+/// content of every file in an archive.
+pub(crate) type FileVisitor<'a> =
+    dyn FnMut(&str, &Path, u64, &mut dyn Read) -> Result<(), Error> + 'a;
+
+/// Walks the `*.tar.gz` written by `FtdcDataService::download_ftdc_data`, including the per host
+/// archives nested in it, and hands every FTDC metrics file to `visit` together with the host it
+/// belongs to.
+pub(crate) fn visit_metrics_files(
+    path: impl AsRef<Path>,
+    mut visit: impl FnMut(&str, &str, &[u8]) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let mut archive = BufReader::new(File::open(path)?);
+    visit_archive(&mut archive, None, &mut visit)
+}
+
+pub(crate) fn visit_archive(
+    archive: &mut dyn Read,
+    nested_in: Option<&str>,
+    visit: &mut MetricsFileVisitor,
+) -> Result<(), Error> {
+    walk_archive(archive, nested_in, &mut |host, path, _, content| {
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        if !is_metrics_file(file_name) {
+            return Ok(());
+        }
+        // The size in the tar header is not trusted for the allocation.
+        let mut bytes = Vec::new();
+        content.read_to_end(&mut bytes)?;
+        visit(host, file_name, &bytes)
+    })
+}
+
+/// Like [`visit_metrics_files`], but hands every file to `visit`, e.g. the logs of a `MONGODB`
+/// job.
+pub(crate) fn visit_files(
+    path: impl AsRef<Path>,
+    mut visit: impl FnMut(&str, &Path, u64, &mut dyn Read) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let mut archive = BufReader::new(File::open(path)?);
+    walk_archive(&mut archive, None, &mut visit)
+}
+
+/// Entries that are not regular files are skipped. Entries with an absolute path or a path
+/// leaving the archive (`..`) are rejected with [`Error::UnsafeArchiveEntry`].
+fn walk_archive(
+    archive: &mut dyn Read,
+    nested_in: Option<&str>,
+    visit: &mut FileVisitor,
+) -> Result<(), Error> {
+    let mut archive = Archive::new(GzDecoder::new(archive));
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        check_entry_path(&path)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+
+        if let Some(archive_name) = strip_archive_extension(file_name) {
+            let archive_name = archive_name.to_string();
+            walk_archive(&mut entry, Some(&archive_name), visit)?;
+        } else {
+            let size = entry.size();
+            let (host, host_path) = split_host(nested_in, &path);
+            visit(&host, &host_path, size, &mut entry)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn check_entry_path(path: &Path) -> Result<(), Error> {
+    let is_safe = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    match is_safe {
+        true => Ok(()),
+        false => Err(Error::UnsafeArchiveEntry(format!(
+            "The archive entry `{}` points outside of the archive",
+            path.display()
+        ))),
+    }
+}
+
+/// The host a metrics file belongs to: the name of the nested per host archive if there is one,
+/// otherwise the directory that holds `diagnostic.data`.
+pub(crate) fn host_label(nested_in: Option<&str>, path: &Path) -> String {
+    split_host(nested_in, path).0
+}
+
+/// The host a file belongs to, see [`host_label`], and the path of the file below the directory
+/// of the host, e.g. `diagnostic.data/metrics.interim` or `logs/mongodb.log`.
+fn split_host(nested_in: Option<&str>, path: &Path) -> (String, PathBuf) {
+    let components: Vec<&str> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+    if let Some(archive_name) = nested_in {
+        return (archive_name.to_string(), components.iter().collect());
+    }
+
+    let directories = &components[..components.len().saturating_sub(1)];
+    let host = match directories {
+        [.., _, "diagnostic.data"] => Some(directories.len() - 2),
+        [.., "diagnostic.data"] | [] => None,
+        [..] => Some(directories.len() - 1),
+    };
+    match host {
+        Some(host) => (
+            components[host].to_string(),
+            components[host + 1..].iter().collect(),
+        ),
+        None => ("unknown".to_string(), components.iter().collect()),
+    }
+}
+
+pub(crate) fn strip_archive_extension(file_name: &str) -> Option<&str> {
+    file_name
+        .strip_suffix(".tar.gz")
+        .or_else(|| file_name.strip_suffix(".tgz"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::path::Path;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use tar::Builder;
+    use tar::Header;
+
+    use crate::archive::check_entry_path;
+    use crate::archive::host_label;
+    use crate::archive::split_host;
+    use crate::error::Error;
+
+    /// Builds a `*.tar.gz` in memory from `(path, content)` pairs.
+    pub(crate) fn tar_gz(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, content) in files {
+            let mut header = Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *content).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn given_paths_when_host_label_then_use_directory_holding_diagnostic_data() {
+        // Given
+        let nested = Path::new("diagnostic.data/metrics.interim");
+        let directory = Path::new("job/host-00_27017/diagnostic.data/metrics.interim");
+        let flat = Path::new("host-01_27017/metrics.interim");
+
+        // When
+        let labels = [
+            host_label(Some("host-02_27017"), nested),
+            host_label(None, directory),
+            host_label(None, flat),
+            host_label(None, nested),
+        ];
+
+        // Then
+        assert_eq!(
+            labels,
+            ["host-02_27017", "host-00_27017", "host-01_27017", "unknown"]
+        );
+    }
+
+    #[test]
+    fn given_paths_when_split_host_then_keep_the_path_below_the_host() {
+        // Given
+        let nested = Path::new("./logs/audit/mongodb.log");
+        let directory = Path::new("job/host-00_27017/diagnostic.data/metrics.interim");
+        let flat = Path::new("host-01_27017/mongodb.log");
+
+        // When
+        let splits = [
+            split_host(Some("host-02_27017"), nested),
+            split_host(None, directory),
+            split_host(None, flat),
+        ];
+
+        // Then
+        assert_eq!(
+            splits,
+            [
+                ("host-02_27017".to_string(), "logs/audit/mongodb.log".into()),
+                (
+                    "host-00_27017".to_string(),
+                    "diagnostic.data/metrics.interim".into()
+                ),
+                ("host-01_27017".to_string(), "mongodb.log".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn given_entries_leaving_the_archive_when_check_entry_path_then_unsafe_archive_entry() {
+        // Given
+        let paths = [
+            "host-00_27017/diagnostic.data/metrics.interim",
+            "./host-00_27017/metrics.interim",
+            "../metrics.interim",
+            "host-00_27017/../../metrics.interim",
+            "/etc/metrics.interim",
+        ];
+
+        // When
+        let checks = paths.map(|path| check_entry_path(Path::new(path)));
+
+        // Then
+        assert!(checks[..2].iter().all(Result::is_ok));
+        assert!(
+            checks[2..]
+                .iter()
+                .all(|check| matches!(check, Err(Error::UnsafeArchiveEntry(_))))
+        );
+    }
+}
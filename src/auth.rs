@@ -0,0 +1,141 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use diqwest::DigestAuthSession;
+use diqwest::WithDigestAuth;
+use reqwest::Client;
+use reqwest::RequestBuilder;
+use reqwest::Response;
+use reqwest::StatusCode;
+use reqwest::header::ACCEPT;
+use reqwest::header::CONTENT_TYPE;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::error::ApiError;
+use crate::error::Error;
+
+/// Tokens are refreshed this long before they expire so they do not run out mid-request.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// How requests against the Atlas Admin API are authenticated.
+pub enum AtlasAuth {
+    /// HTTP digest authentication with the public and private key of an Atlas API key.
+    Digest(DigestAuthSession),
+    /// Bearer tokens of an Atlas service account, issued via the OAuth2 client credentials flow.
+    ServiceAccount(ServiceAccount),
+}
+
+impl AtlasAuth {
+    pub fn digest(public: &str, private: &str) -> Self {
+        AtlasAuth::Digest(DigestAuthSession::new(public, private))
+    }
+
+    pub fn service_account(client_id: &str, client_secret: &str) -> Self {
+        AtlasAuth::ServiceAccount(ServiceAccount::new(client_id, client_secret))
+    }
+
+    /// Sends `request` authenticated, `token_url` is where service account tokens are issued.
+    pub(crate) async fn send(
+        &self,
+        client: &Client,
+        token_url: &str,
+        request: RequestBuilder,
+    ) -> Result<Response, Error> {
+        match self {
+            AtlasAuth::Digest(session) => Ok(request.send_digest_auth(session).await?),
+            AtlasAuth::ServiceAccount(account) => {
+                let retry = request.try_clone();
+                let token = account.access_token(client, token_url).await?;
+                let response = request.bearer_auth(token).send().await?;
+
+                // The token might have been revoked before it expired: fetch a new one once.
+                match (response.status(), retry) {
+                    (StatusCode::UNAUTHORIZED, Some(retry)) => {
+                        account.invalidate().await;
+                        let token = account.access_token(client, token_url).await?;
+                        Ok(retry.bearer_auth(token).send().await?)
+                    }
+                    _ => Ok(response),
+                }
+            }
+        }
+    }
+}
+
+/// The client id and secret of an Atlas service account together with the cached access token.
+pub struct ServiceAccount {
+    client_id: String,
+    client_secret: String,
+    token: Mutex<Option<AccessToken>>,
+}
+
+struct AccessToken {
+    value: String,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TokenResponse {
+    pub(crate) access_token: String,
+    pub(crate) expires_in: u64,
+}
+
+impl ServiceAccount {
+    pub fn new(client_id: &str, client_secret: &str) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Returns the cached access token or fetches a new one if there is none or it is about to
+    /// expire.
+    async fn access_token(&self, client: &Client, token_url: &str) -> Result<String, Error> {
+        let mut token = self.token.lock().await;
+        if let Some(token) = token.as_ref().filter(|t| t.expires_at > Instant::now()) {
+            return Ok(token.value.clone());
+        }
+
+        let response = client
+            .post(token_url)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .header(ACCEPT, "application/json")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body("grant_type=client_credentials")
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let response_body = response.text().await?;
+                let TokenResponse { access_token, expires_in } =
+                    serde_json::from_str(&response_body)?;
+                let lifetime = Duration::from_secs(expires_in).saturating_sub(TOKEN_EXPIRY_MARGIN);
+
+                *token = Some(AccessToken {
+                    value: access_token.clone(),
+                    expires_at: Instant::now() + lifetime,
+                });
+                Ok(access_token)
+            }
+            _ => Err(Error::Authentication(
+                ApiError::from_response(
+                    response,
+                    format!(
+                        "Something went wrong fetching an access token for service account {client_id}",
+                        client_id = self.client_id
+                    ),
+                    None,
+                )
+                .await?,
+            )),
+        }
+    }
+
+    async fn invalidate(&self) {
+        *self.token.lock().await = None;
+    }
+}
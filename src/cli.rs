@@ -1,30 +1,457 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::Args;
 use clap::Parser;
+use clap::Subcommand;
+use ftdc::auth::AtlasAuth;
+use ftdc::error::Error;
+use ftdc::export::ExportConfig;
+use ftdc::export::ExportFormat;
+use ftdc::export::Layout;
+use ftdc::export::MetricFilter;
+use ftdc::model::LogType;
+use ftdc::openmetrics::DEFAULT_PREFIX;
+use ftdc::openmetrics::OpenMetricsConfig;
+use ftdc::output::DEFAULT_FILE_NAME_TEMPLATE;
+use ftdc::output::OutputConfig;
+use ftdc::poll::PollConfig;
+use ftdc::retry::RetryConfig;
+use ftdc::service::ApiFlavor;
+use ftdc::service::ApiVersion;
+use ftdc::service::DEFAULT_API_VERSION_DATE;
+
+/// Bounds `jobs extend --days`, so the expiration date cannot overflow.
+const MAX_EXTEND_DAYS: u64 = 3_650;
 
 /// Loading FTDC data (full time diagnostic data capture) from a particular replica set or dedicated
 /// shard of a sharded clutser to investigate deeper (e.g. with keyhole).
 #[derive(Parser)]
 #[clap(author, version, long_about)]
 pub(crate) struct Cli {
+    #[clap(subcommand)]
+    pub(crate) command: Command,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum Command {
+    /// Create a log collection job, wait for it to finish and download its data in one go.
+    Fetch(FetchArgs),
+    /// Manage log collection jobs step by step.
+    #[clap(subcommand)]
+    Jobs(JobsCommand),
+    /// Discover the clusters of the project.
+    #[clap(subcommand)]
+    Clusters(ClustersCommand),
+    /// Extract a downloaded archive into `<output dir>/<replica set>/<host:port>/diagnostic.data/`
+    /// and write a `manifest.json` with the files and time ranges per host.
+    Extract(ExtractArgs),
+    /// Export decoded FTDC metrics of a downloaded archive or a `diagnostic.data` directory to CSV
+    /// or Parquet.
+    Export(ExportArgs),
+    /// Write decoded FTDC metrics of a downloaded archive or a `diagnostic.data` directory as
+    /// OpenMetrics text, e.g. for `promtool tsdb create-blocks-from openmetrics`.
+    #[clap(name = "openmetrics")]
+    OpenMetrics(OpenMetricsArgs),
+}
+
+#[derive(Args)]
+pub(crate) struct ExportArgs {
+    /// The downloaded `*.tar.gz` archive or a local `diagnostic.data` directory.
+    pub(crate) input: PathBuf,
+    /// The file to write to. CSV is written to stdout if not given.
+    #[clap(long, short)]
+    pub(crate) output: Option<PathBuf>,
+    /// `csv` or `parquet`. Defaults to `parquet` for an output file ending in `.parquet`, `csv`
+    /// otherwise.
+    #[clap(long, value_parser = ExportFormat::from_str)]
+    pub(crate) format: Option<ExportFormat>,
+    /// `wide` for a column per metric or `long` for the columns `timestamp`, `host`, `metric` and
+    /// `value`.
+    #[clap(long, default_value = "wide", value_parser = Layout::from_str)]
+    pub(crate) layout: Layout,
+    #[clap(flatten)]
+    pub(crate) metrics: MetricArgs,
+}
+
+#[derive(Args)]
+pub(crate) struct OpenMetricsArgs {
+    /// The downloaded `*.tar.gz` archive or a local `diagnostic.data` directory.
+    pub(crate) input: PathBuf,
+    /// The file to write to. Written to stdout if not given.
+    #[clap(long, short)]
+    pub(crate) output: Option<PathBuf>,
+    #[clap(flatten)]
+    pub(crate) metrics: MetricArgs,
+    /// The `replica_set` label. Taken from the archive name if it follows the default
+    /// `{log_type}_data_{replica_set}_job_{job_id}.tar.gz`.
+    #[clap(long, short)]
+    pub(crate) replica_set_name: Option<String>,
+    /// The `job_id` label. Taken from the archive name like the replica set.
+    #[clap(long)]
+    pub(crate) job_id: Option<String>,
+    /// Prepended to every metric name.
+    #[clap(long, default_value = DEFAULT_PREFIX)]
+    pub(crate) prefix: String,
+}
+
+#[derive(Args)]
+pub(crate) struct MetricArgs {
+    /// A glob on the dotted metric path, e.g. `serverStatus.connections.*`. `*` matches within a
+    /// segment, `**` across segments. Repeat it to select several. All metrics without a pattern.
+    #[clap(long = "metric")]
+    pub(crate) metrics: Vec<String>,
+    /// A regular expression on the dotted metric path. Can be combined with `--metric`.
+    #[clap(long = "regex")]
+    pub(crate) regexes: Vec<String>,
+}
+
+#[derive(Args)]
+pub(crate) struct ExtractArgs {
+    /// The downloaded `*.tar.gz` archive.
+    pub(crate) archive: PathBuf,
+    /// The directory to extract to. Defaults to the current directory.
+    #[clap(long, short, alias = "output")]
+    pub(crate) output_dir: Option<PathBuf>,
+    /// The replica set the data belongs to. Taken from the archive name if it follows the default
+    /// `{log_type}_data_{replica_set}_job_{job_id}.tar.gz`, otherwise the archive name is used.
+    #[clap(long, short)]
+    pub(crate) replica_set_name: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ClustersCommand {
+    /// List the replica sets and mongos processes of the project with their cluster and type.
+    List {
+        #[clap(flatten)]
+        atlas: AtlasArgs,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum JobsCommand {
+    /// List the log collection jobs of the project.
+    List {
+        #[clap(flatten)]
+        atlas: AtlasArgs,
+        /// Only list jobs in this state (`SUCCESS`, `FAILURE`, `IN_PROGRESS`,
+        /// `MARKED_FOR_EXPIRY` or `EXPIRED`).
+        #[clap(long)]
+        status: Option<String>,
+        /// Also list the collection per host of every job.
+        #[clap(long, short)]
+        verbose: bool,
+    },
+    /// Create log collection jobs without waiting for them and print their ids.
+    Create {
+        #[clap(flatten)]
+        atlas: AtlasArgs,
+        #[clap(flatten)]
+        target: TargetArgs,
+    },
+    /// Show the state of a log collection job.
+    Status {
+        /// The id of the job.
+        job_id: String,
+        #[clap(flatten)]
+        atlas: AtlasArgs,
+    },
+    /// Wait for a log collection job to finish and download its data.
+    Download {
+        /// The id of the job.
+        job_id: String,
+        #[clap(flatten)]
+        atlas: AtlasArgs,
+        #[clap(flatten)]
+        poll: PollArgs,
+        #[clap(flatten)]
+        output: OutputArgs,
+    },
+    /// Extend how long the data of a log collection job can be downloaded.
+    Extend {
+        /// The id of the job.
+        job_id: String,
+        /// The number of days from now on the data of the job should be kept, at most ten years.
+        #[clap(long, value_parser = clap::value_parser!(u64).range(1..=MAX_EXTEND_DAYS))]
+        days: u64,
+        #[clap(flatten)]
+        atlas: AtlasArgs,
+    },
+    /// Retry the failed collections of a log collection job.
+    Retry {
+        /// The id of the job.
+        job_id: String,
+        #[clap(flatten)]
+        atlas: AtlasArgs,
+    },
+    /// Delete a log collection job. A job that is still in progress is cancelled.
+    Delete {
+        /// The id of the job.
+        job_id: String,
+        #[clap(flatten)]
+        atlas: AtlasArgs,
+    },
+}
+
+#[derive(Args)]
+pub(crate) struct FetchArgs {
+    #[clap(flatten)]
+    pub(crate) atlas: AtlasArgs,
+    #[clap(flatten)]
+    pub(crate) target: TargetArgs,
+    #[clap(flatten)]
+    pub(crate) poll: PollArgs,
+    #[clap(flatten)]
+    pub(crate) output: OutputArgs,
+    /// Download a successful, unexpired job for the same replica set that was created within this
+    /// many minutes instead of creating a new job.
+    #[clap(long)]
+    pub(crate) reuse_jobs_within: Option<u64>,
+    /// Retry a job once if it fails before giving up.
+    #[clap(long)]
+    pub(crate) retry_failed_job: bool,
+}
+
+/// Which project to talk to and how to authenticate.
+#[derive(Args)]
+pub(crate) struct AtlasArgs {
     /// The group key (or: project id) the respective cluster belongs too. It is encoded into
     /// the link you get from atlas when selecting the specific cluster on Atlas UI (e.g.
     /// `cloud.mongodb.com/v2/{group key}#clusters`).
     #[clap(long, short)]
     pub(crate) group_key: String,
+    /// The public key of your Atlas API key.
+    #[clap(
+        long,
+        env = "ATLAS_PUBLIC_KEY",
+        required_unless_present = "client_id",
+        requires = "atlas_private_key"
+    )]
+    pub(crate) atlas_public_key: Option<String>,
+    /// The private key of your Atlas API key.
+    #[clap(long, env = "ATLAS_PRIVATE_KEY", requires = "atlas_public_key")]
+    pub(crate) atlas_private_key: Option<String>,
+    /// The client id of an Atlas service account. Use it together with `--client-secret` as an
+    /// alternative to an API key.
+    #[clap(long, env = "ATLAS_CLIENT_ID", requires = "client_secret")]
+    pub(crate) client_id: Option<String>,
+    /// The client secret of an Atlas service account.
+    #[clap(long, env = "ATLAS_CLIENT_SECRET", requires = "client_id")]
+    pub(crate) client_secret: Option<String>,
+    /// The resource version of the Atlas Admin API v2 to request (e.g. `2023-01-01`).
+    #[clap(long, env = "ATLAS_API_VERSION", default_value = DEFAULT_API_VERSION_DATE)]
+    pub(crate) api_version: String,
+    /// Talk to the deprecated Atlas Admin API v1.0 instead of v2. Only available until MongoDB
+    /// shuts the v1.0 API off. Overrides `--api-version` and `ATLAS_API_VERSION`.
+    #[clap(long)]
+    pub(crate) legacy_api: bool,
+    /// The API to talk to: `atlas`, `atlas-gov` (Atlas for Government), `cloud-manager` or
+    /// `ops-manager`, in any case. Cloud and Ops Manager ignore the API version.
+    #[clap(
+        long,
+        env = "ATLAS_FLAVOR",
+        default_value = "atlas",
+        ignore_case = true,
+        value_parser = ApiFlavor::from_str
+    )]
+    pub(crate) flavor: ApiFlavor,
+    /// The URL the API is served at, e.g. the URL of your Ops Manager. Defaults to the URL of the
+    /// flavor (`https://cloud.mongodb.com` for Atlas and Cloud Manager,
+    /// `https://cloud.mongodbgov.com` for Atlas for Government).
+    #[clap(long, env = "ATLAS_BASE_URL", required_if_eq("flavor", "ops-manager"))]
+    pub(crate) base_url: Option<String>,
+    /// How often a request is attempted if Atlas rate limits it or fails temporarily. Creating a
+    /// job is only sent again if Atlas rate limited it.
+    #[clap(long, default_value = "4")]
+    pub(crate) max_attempts: u32,
+}
+
+/// What to collect FTDC data of.
+#[derive(Args)]
+pub(crate) struct TargetArgs {
     /// The name of the replica set the data should be loaded from. You can either provide the
-    /// direct targeted replica set name (e.g. `atlas-<something>-shard-0`) or the name of the
-    /// shard (e.g. `some-name-shard-00`). Do not forget the number here as it qualifies the
-    /// shard in case you want data from a sharded cluster. For a standalone replica set it is
-    ///`00` but lets say for a sharded cluster with 3 shards it would be `00`, `01`, `02`.
+    /// exact replica set name (e.g. `atlas-<something>-shard-0`) or the name of the shard (e.g.
+    /// `some-name-shard-00`). Do not forget the number here as it qualifies the shard in case you
+    /// want data from a sharded cluster. For a standalone replica set it is `00` but lets say for
+    /// a sharded cluster with 3 shards it would be `00`, `01`, `02`. A name that matches several
+    /// replica sets is rejected. If neither this nor `--cluster-name` is given on a terminal, you
+    /// pick from the replica sets of the project.
     #[clap(long, short)]
-    pub(crate) replica_set_name: String,
+    pub(crate) replica_set_name: Option<String>,
+    /// The name of a cluster to download the FTDC data of every shard and the config server of in
+    /// one run. Can be used instead of `--replica-set-name`. Only available on Atlas and Atlas for
+    /// Government, Cloud and Ops Manager hosts do not carry the cluster name.
+    #[clap(long, short, conflicts_with = "replica_set_name")]
+    pub(crate) cluster_name: Option<String>,
+    /// Also download the FTDC data of the mongos processes of the cluster given with
+    /// `--cluster-name`.
+    #[clap(long, requires = "cluster_name", conflicts_with = "shard")]
+    pub(crate) include_mongos: bool,
+    /// Only download the FTDC data of the shard with this index (`0` for `<cluster>-shard-00`) of
+    /// the cluster given with `--cluster-name`. A replica set cluster has the single shard `0`.
+    #[clap(long, requires = "cluster_name")]
+    pub(crate) shard: Option<u32>,
     /// Optional byte size of the downloaded job. If the data in your job is not going back in
     /// time enough: increase the byte size.
     #[clap(long, short, default_value = "10000000")]
     pub(crate) size: u64,
-    /// The public key of your Atlas API key.
-    #[clap(long, env = "ATLAS_PUBLIC_KEY")]
-    pub(crate) atlas_public_key: String,
-    /// The private key of your Atlas API key.
-    #[clap(long, env = "ATLAS_PRIVATE_KEY")]
-    pub(crate) atlas_private_key: String,
+    /// The kind of logs to collect: `FTDC`, `MONGODB`, `AUTOMATION_AGENT`, `BACKUP_AGENT` or
+    /// `MONITORING_AGENT`. Repeat it to collect several kinds, each in a job and archive of its
+    /// own.
+    #[clap(long = "log-type", default_value = "FTDC")]
+    pub(crate) log_types: Vec<String>,
+    /// Redact the collected logs. This is the default.
+    #[clap(long, overrides_with = "no_redacted")]
+    pub(crate) redacted: bool,
+    /// Collect unredacted logs. They may contain sensitive information like query values, so you
+    /// are asked to confirm. The file names of unredacted data are prefixed with `unredacted_`.
+    #[clap(long, overrides_with = "redacted")]
+    pub(crate) no_redacted: bool,
+    /// Do not ask for confirmation before collecting unredacted logs.
+    #[clap(long, short)]
+    pub(crate) yes: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct PollArgs {
+    /// Seconds to wait between two checks of the job state. The interval grows with every check
+    /// up to 30 seconds.
+    #[clap(long, default_value = "3", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) poll_interval: u64,
+    /// Give up waiting for the job to finish after this many seconds.
+    #[clap(long)]
+    pub(crate) poll_timeout: Option<u64>,
+}
+
+#[derive(Args)]
+pub(crate) struct OutputArgs {
+    /// The directory to download to. Defaults to the current directory.
+    #[clap(long, short, alias = "output")]
+    pub(crate) output_dir: Option<PathBuf>,
+    /// The name of the downloaded file. The placeholders `{group}`, `{cluster}`, `{replica_set}`,
+    /// `{job_id}`, `{log_type}`, `{redaction}` (`redacted` or `unredacted`) and `{timestamp}` are
+    /// replaced. Slashes create subdirectories. Unredacted data is prefixed with `unredacted_`
+    /// unless `{redaction}` is used.
+    #[clap(long, default_value = DEFAULT_FILE_NAME_TEMPLATE)]
+    pub(crate) file_name: String,
+    /// Overwrite files that already exist instead of failing.
+    #[clap(long)]
+    pub(crate) force: bool,
+}
+
+impl AtlasArgs {
+    /// The service account takes precedence if both, an API key and a service account, are given.
+    pub(crate) fn auth(&self) -> AtlasAuth {
+        match (
+            &self.client_id,
+            &self.client_secret,
+            &self.atlas_public_key,
+            &self.atlas_private_key,
+        ) {
+            (Some(id), Some(secret), _, _) => AtlasAuth::service_account(id, secret),
+            (_, _, Some(public), Some(private)) => AtlasAuth::digest(public, private),
+            _ => unreachable!("clap requires either a service account or an API key"),
+        }
+    }
+
+    pub(crate) fn retry_config(&self) -> RetryConfig {
+        RetryConfig::default().with_max_attempts(self.max_attempts.max(1))
+    }
+
+    pub(crate) fn api_version(&self) -> ApiVersion {
+        match self.legacy_api {
+            true => ApiVersion::V1,
+            false => ApiVersion::V2(self.api_version.clone()),
+        }
+    }
+}
+
+impl TargetArgs {
+    pub(crate) fn log_types(&self) -> Result<Vec<LogType>, Error> {
+        let log_types: BTreeSet<LogType> = self
+            .log_types
+            .iter()
+            .map(|log_type| LogType::from_str(log_type))
+            .collect::<Result<_, _>>()?;
+        Ok(log_types.into_iter().collect())
+    }
+
+    pub(crate) fn redacted(&self) -> bool {
+        !self.no_redacted
+    }
+}
+
+impl MetricArgs {
+    pub(crate) fn filter(&self) -> Result<MetricFilter, Error> {
+        let filter = self
+            .metrics
+            .iter()
+            .try_fold(MetricFilter::default(), |filter, glob| {
+                filter.with_glob(glob)
+            })?;
+        self.regexes
+            .iter()
+            .try_fold(filter, |filter, regex| filter.with_regex(regex))
+    }
+}
+
+impl ExportArgs {
+    pub(crate) fn export_config(&self) -> Result<ExportConfig, Error> {
+        let filter = self.metrics.filter()?;
+        let is_parquet_file = self
+            .output
+            .as_ref()
+            .is_some_and(|output| output.extension().is_some_and(|ext| ext == "parquet"));
+        let format = match (self.format, is_parquet_file) {
+            (Some(format), _) => format,
+            (None, true) => ExportFormat::Parquet,
+            (None, false) => ExportFormat::Csv,
+        };
+
+        Ok(ExportConfig::default()
+            .with_filter(filter)
+            .with_layout(self.layout)
+            .with_format(format))
+    }
+}
+
+impl OpenMetricsArgs {
+    pub(crate) fn openmetrics_config(&self) -> Result<OpenMetricsConfig, Error> {
+        let config = OpenMetricsConfig::default()
+            .with_filter(self.metrics.filter()?)
+            .with_prefix(&self.prefix);
+        let config = match &self.replica_set_name {
+            Some(replica_set) => config.with_replica_set(replica_set),
+            None => config,
+        };
+        Ok(match &self.job_id {
+            Some(job_id) => config.with_job_id(job_id),
+            None => config,
+        })
+    }
+}
+
+impl PollArgs {
+    pub(crate) fn poll_config(&self) -> PollConfig {
+        let poll_config =
+            PollConfig::default().with_interval(Duration::from_secs(self.poll_interval));
+        match self.poll_timeout {
+            Some(timeout) => poll_config.with_timeout(Duration::from_secs(timeout)),
+            None => poll_config,
+        }
+    }
+}
+
+impl OutputArgs {
+    pub(crate) fn output_config(&self) -> OutputConfig {
+        let output_config = OutputConfig::default()
+            .with_file_name_template(&self.file_name)
+            .with_overwrite(self.force);
+        match &self.output_dir {
+            Some(output_dir) => output_config.with_directory(output_dir),
+            None => output_config,
+        }
+    }
 }
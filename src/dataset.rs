@@ -0,0 +1,352 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use bson::DateTime;
+
+use crate::archive;
+use crate::decoder;
+use crate::decoder::FtdcDocument;
+use crate::decoder::FtdcReader;
+use crate::decoder::MetricChunk;
+use crate::error::Error;
+
+const START: &str = "start";
+const SECTION_START: &str = ".start";
+
+/// All samples of one metric of one host, ordered by time with one sample per timestamp.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSeries {
+    pub host: String,
+    pub path: String,
+    pub timestamps: Vec<DateTime>,
+    pub values: Vec<i64>,
+}
+
+impl MetricSeries {
+    fn new(host: &str, path: &str) -> Self {
+        Self {
+            host: host.to_string(),
+            path: path.to_string(),
+            timestamps: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = (DateTime, i64)> + '_ {
+        self.timestamps
+            .iter()
+            .copied()
+            .zip(self.values.iter().copied())
+    }
+
+    /// Samples taken at or after `from` and before `to`.
+    pub fn between(
+        &self,
+        from: DateTime,
+        to: DateTime,
+    ) -> impl Iterator<Item = (DateTime, i64)> + '_ {
+        let first = self
+            .timestamps
+            .partition_point(|timestamp| *timestamp < from);
+        let last = self.timestamps.partition_point(|timestamp| *timestamp < to);
+        self.samples().skip(first).take(last.saturating_sub(first))
+    }
+
+    /// Orders the samples by time and keeps the first of several samples with the same timestamp,
+    /// e.g. of a `metrics.interim` that overlaps the last complete metrics file.
+    fn sort_and_dedup(&mut self) {
+        if self.timestamps.is_sorted_by(|a, b| a < b) {
+            return;
+        }
+        let mut samples: Vec<(DateTime, i64)> = self.samples().collect();
+        samples.sort_by_key(|(timestamp, _)| *timestamp);
+        samples.dedup_by_key(|(timestamp, _)| *timestamp);
+        (self.timestamps, self.values) = samples.into_iter().unzip();
+    }
+}
+
+/// Decoded FTDC metrics of one or more hosts, keyed by host and dotted metric path (e.g.
+/// `serverStatus.wiredTiger.cache.bytes currently in the cache`). The timestamp of a sample is
+/// taken from the `start` field of the sampled document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FtdcDataset {
+    series: BTreeMap<(String, String), MetricSeries>,
+}
+
+impl FtdcDataset {
+    /// Decodes all metrics files of the `*.tar.gz` written by `download_ftdc_data`.
+    pub fn from_archive(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut dataset = FtdcDataset::default();
+        archive::visit_metrics_files(path, |host, _, bytes| dataset.add_file(host, bytes))?;
+        Ok(dataset.sorted())
+    }
+
+    /// Decodes all metrics files of a local `diagnostic.data` directory.
+    pub fn from_dir(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let host = archive::host_label(None, &path.join("metrics"));
+
+        let mut dataset = FtdcDataset::default();
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry
+                .file_name()
+                .to_str()
+                .is_some_and(decoder::is_metrics_file)
+            {
+                dataset.add_file(&host, &fs::read(entry.path())?)?;
+            }
+        }
+        Ok(dataset.sorted())
+    }
+
+    /// Builds a dataset from already decoded documents of a single host.
+    pub fn from_documents(
+        host: &str,
+        documents: impl IntoIterator<Item = FtdcDocument>,
+    ) -> Result<Self, Error> {
+        let mut dataset = FtdcDataset::default();
+        dataset.add_documents(host, documents)?;
+        Ok(dataset.sorted())
+    }
+
+    pub fn hosts(&self) -> BTreeSet<&str> {
+        self.series.keys().map(|(host, _)| host.as_str()).collect()
+    }
+
+    pub fn metric_paths(&self) -> BTreeSet<&str> {
+        self.series.keys().map(|(_, path)| path.as_str()).collect()
+    }
+
+    pub fn series(&self, host: &str, path: &str) -> Option<&MetricSeries> {
+        self.series.get(&(host.to_string(), path.to_string()))
+    }
+
+    /// The series of a metric for every host that reported it.
+    pub fn metric<'a>(&'a self, path: &'a str) -> impl Iterator<Item = &'a MetricSeries> + 'a {
+        self.series
+            .values()
+            .filter(move |series| series.path == path)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MetricSeries> {
+        self.series.values()
+    }
+
+    /// The timestamps of the first and the last sample of any host, `None` if there are none.
+    pub fn time_range(&self) -> Option<(DateTime, DateTime)> {
+        let first = self.iter().filter_map(|s| s.timestamps.first()).min()?;
+        let last = self.iter().filter_map(|s| s.timestamps.last()).max()?;
+        Some((*first, *last))
+    }
+
+    fn add_file(&mut self, host: &str, bytes: &[u8]) -> Result<(), Error> {
+        let documents = FtdcReader::new(bytes).collect::<Result<Vec<_>, _>>()?;
+        self.add_documents(host, documents)
+    }
+
+    fn add_documents(
+        &mut self,
+        host: &str,
+        documents: impl IntoIterator<Item = FtdcDocument>,
+    ) -> Result<(), Error> {
+        documents
+            .into_iter()
+            .try_for_each(|document| match document {
+                FtdcDocument::MetricChunk(chunk) => self.add_chunk(host, &chunk),
+                FtdcDocument::Metadata(_) | FtdcDocument::PeriodicMetadata(_) => Ok(()),
+            })
+    }
+
+    fn add_chunk(&mut self, host: &str, chunk: &MetricChunk) -> Result<(), Error> {
+        let timestamps = chunk
+            .metric(START)
+            .or_else(|| {
+                chunk
+                    .metrics
+                    .iter()
+                    .find(|metric| metric.path.ends_with(SECTION_START))
+            })
+            .ok_or_else(|| {
+                Error::Decode(format!(
+                    "FTDC metric chunk of host {host} has no `start` field"
+                ))
+            })?
+            .values
+            .iter()
+            .map(|millis| DateTime::from_millis(*millis))
+            .collect::<Vec<_>>();
+
+        for metric in &chunk.metrics {
+            let series = self
+                .series
+                .entry((host.to_string(), metric.path.clone()))
+                .or_insert_with(|| MetricSeries::new(host, &metric.path));
+            series.timestamps.extend_from_slice(&timestamps);
+            series.values.extend_from_slice(&metric.values);
+        }
+
+        Ok(())
+    }
+
+    fn sorted(mut self) -> Self {
+        self.series
+            .values_mut()
+            .for_each(MetricSeries::sort_and_dedup);
+        self
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use bson::DateTime;
+    use bson::doc;
+
+    use crate::archive;
+    use crate::archive::tests::tar_gz;
+    use crate::dataset::FtdcDataset;
+    use crate::decoder::tests::chunk_document;
+
+    /// A metrics file with three samples a second apart, `connections` growing by one.
+    pub(crate) fn metrics_file(start: i64, connections: i32) -> Vec<u8> {
+        let reference = doc! {
+            "start": DateTime::from_millis(start),
+            "serverStatus": { "connections": { "current": connections } },
+        };
+        let mut file = Vec::new();
+        chunk_document(start, &reference, &[vec![1_000, 1_000], vec![1, 1]])
+            .to_writer(&mut file)
+            .unwrap();
+        file
+    }
+
+    fn dataset_from(archive: Vec<u8>) -> FtdcDataset {
+        let mut dataset = FtdcDataset::default();
+        archive::visit_archive(&mut Cursor::new(archive), None, &mut |host, _, bytes| {
+            dataset.add_file(host, bytes)
+        })
+        .unwrap();
+        dataset.sorted()
+    }
+
+    #[test]
+    fn given_nested_host_archives_when_load_dataset_then_get_series_per_host() {
+        // Given
+        let host_00 = tar_gz(&[("diagnostic.data/metrics.interim", &metrics_file(10_000, 5))]);
+        let host_01 = tar_gz(&[("diagnostic.data/metrics.2024", &metrics_file(20_000, 7))]);
+        let job = tar_gz(&[
+            ("host-00_27017.tar.gz", &host_00),
+            ("host-01_27017.tar.gz", &host_01),
+        ]);
+
+        // When
+        let dataset = dataset_from(job);
+
+        // Then
+        assert_eq!(
+            dataset.hosts().into_iter().collect::<Vec<_>>(),
+            ["host-00_27017", "host-01_27017"]
+        );
+        let series = dataset
+            .series("host-01_27017", "serverStatus.connections.current")
+            .unwrap();
+        assert_eq!(
+            series.samples().collect::<Vec<_>>(),
+            vec![
+                (DateTime::from_millis(20_000), 7),
+                (DateTime::from_millis(21_000), 8),
+                (DateTime::from_millis(22_000), 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn given_files_out_of_order_when_between_then_get_samples_in_time_range() {
+        // Given
+        let job = tar_gz(&[
+            (
+                "host-00_27017/diagnostic.data/metrics.interim",
+                &metrics_file(13_000, 20),
+            ),
+            (
+                "host-00_27017/diagnostic.data/metrics.2024",
+                &metrics_file(10_000, 10),
+            ),
+        ]);
+        let dataset = dataset_from(job);
+
+        // When
+        let samples: Vec<(DateTime, i64)> = dataset
+            .metric("serverStatus.connections.current")
+            .flat_map(|series| {
+                series.between(DateTime::from_millis(11_000), DateTime::from_millis(14_000))
+            })
+            .collect();
+
+        // Then
+        assert_eq!(
+            samples,
+            vec![
+                (DateTime::from_millis(11_000), 11),
+                (DateTime::from_millis(12_000), 12),
+                (DateTime::from_millis(13_000), 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn given_interim_file_overlapping_a_metrics_file_when_load_dataset_then_drop_duplicates() {
+        // Given
+        let job = tar_gz(&[
+            (
+                "host-00_27017/diagnostic.data/metrics.2024",
+                &metrics_file(10_000, 10),
+            ),
+            (
+                "host-00_27017/diagnostic.data/metrics.interim",
+                &metrics_file(12_000, 12),
+            ),
+        ]);
+
+        // When
+        let dataset = dataset_from(job);
+
+        // Then
+        let series = dataset
+            .series("host-00_27017", "serverStatus.connections.current")
+            .unwrap();
+        assert_eq!(
+            series.timestamps,
+            [10_000, 11_000, 12_000, 13_000, 14_000].map(DateTime::from_millis)
+        );
+        assert_eq!(series.values, [10, 11, 12, 13, 14]);
+    }
+
+    #[test]
+    fn given_chunk_without_start_when_load_dataset_then_decode_error() {
+        // Given
+        let mut file = Vec::new();
+        chunk_document(1, &doc! { "uptime": 1 }, &[vec![1]])
+            .to_writer(&mut file)
+            .unwrap();
+        let mut dataset = FtdcDataset::default();
+
+        // When
+        let error = dataset.add_file("host", &file).unwrap_err().to_string();
+
+        // Then
+        assert_eq!(error, "FTDC metric chunk of host host has no `start` field");
+    }
+}
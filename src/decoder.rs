@@ -0,0 +1,553 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::path::Path;
+
+use bson::Bson;
+use bson::DateTime;
+use bson::Document;
+use flate2::read::ZlibDecoder;
+
+use crate::error::Error;
+
+const METADATA: i32 = 0;
+const METRIC_CHUNK: i32 = 1;
+const PERIODIC_METADATA: i32 = 2;
+
+/// The maximum size of a BSON document, larger lengths in a metrics file are corrupt.
+const MAX_DOCUMENT_LENGTH: i32 = 16 * 1024 * 1024;
+
+/// The maximum number of deltas (metrics times samples) of a chunk. mongod writes a few hundred
+/// samples of a few thousand metrics per chunk, larger counts in a chunk header are corrupt.
+const MAX_CHUNK_DELTAS: u64 = 64 * 1024 * 1024;
+
+/// A single top level document of an FTDC metrics file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FtdcDocument {
+    /// Type 0: static metadata written once at the beginning of a file (e.g. `hostInfo`).
+    Metadata(Metadata),
+    /// Type 1: a compressed chunk of metric samples.
+    MetricChunk(MetricChunk),
+    /// Type 2: metadata that is collected periodically (newer server versions only).
+    PeriodicMetadata(Metadata),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    pub id: DateTime,
+    pub document: Document,
+}
+
+/// A decoded metric chunk: the reference document and every metric found in it with one value
+/// per sample. The first value of each metric is the one of the reference document itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricChunk {
+    pub id: DateTime,
+    pub reference: Document,
+    pub metrics: Vec<Metric>,
+}
+
+impl MetricChunk {
+    /// Number of samples in this chunk, including the reference document.
+    pub fn sample_count(&self) -> usize {
+        self.metrics.first().map_or(0, |metric| metric.values.len())
+    }
+
+    pub fn metric(&self, path: &str) -> Option<&Metric> {
+        self.metrics.iter().find(|metric| metric.path == path)
+    }
+}
+
+/// A metric of a chunk, addressed by its dotted path in the reference document (e.g.
+/// `serverStatus.connections.current`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    pub path: String,
+    pub values: Vec<i64>,
+}
+
+/// Returns `true` for the file names mongod uses for FTDC data in `diagnostic.data`, that is
+/// `metrics.<timestamp>` and `metrics.interim`.
+pub fn is_metrics_file(file_name: &str) -> bool {
+    file_name.starts_with("metrics.")
+}
+
+/// Reads and decodes all documents of an FTDC metrics file.
+pub fn read_file(path: impl AsRef<Path>) -> Result<Vec<FtdcDocument>, Error> {
+    FtdcReader::new(BufReader::new(File::open(path)?)).collect()
+}
+
+/// Iterates over the BSON documents of an FTDC metrics file and decodes them one by one.
+pub struct FtdcReader<R> {
+    reader: R,
+}
+
+impl<R: Read> FtdcReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    fn next_document(&mut self) -> Result<Option<Document>, Error> {
+        let mut length = [0u8; 4];
+        match self.reader.read_exact(&mut length) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+
+        let document_length = i32::from_le_bytes(length);
+        if !(5..=MAX_DOCUMENT_LENGTH).contains(&document_length) {
+            return Err(Error::Decode(format!(
+                "Invalid BSON document length: {document_length}"
+            )));
+        }
+
+        let mut bytes = vec![0u8; document_length as usize];
+        bytes[..4].copy_from_slice(&length);
+        self.reader.read_exact(&mut bytes[4..])?;
+
+        Ok(Some(Document::from_reader(bytes.as_slice())?))
+    }
+}
+
+impl<R: Read> Iterator for FtdcReader<R> {
+    type Item = Result<FtdcDocument, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_document()
+            .transpose()
+            .map(|document| document.and_then(decode_document))
+    }
+}
+
+fn decode_document(document: Document) -> Result<FtdcDocument, Error> {
+    let id = document
+        .get_datetime("_id")
+        .map_err(|e| Error::Decode(format!("FTDC document without a valid `_id`: {e}")))?
+        .to_owned();
+    let document_type = document
+        .get_i32("type")
+        .map_err(|e| Error::Decode(format!("FTDC document without a valid `type`: {e}")))?;
+
+    let metadata = |document: &Document| {
+        document
+            .get_document("doc")
+            .map(|doc| Metadata { id, document: doc.clone() })
+            .map_err(|e| Error::Decode(format!("FTDC metadata without a valid `doc`: {e}")))
+    };
+
+    match document_type {
+        METADATA => metadata(&document).map(FtdcDocument::Metadata),
+        PERIODIC_METADATA => metadata(&document).map(FtdcDocument::PeriodicMetadata),
+        METRIC_CHUNK => {
+            let data = document
+                .get_binary_generic("data")
+                .map_err(|e| Error::Decode(format!("FTDC metric chunk without `data`: {e}")))?;
+            let (reference, metrics) = decode_chunk(data)?;
+
+            Ok(FtdcDocument::MetricChunk(MetricChunk {
+                id,
+                reference,
+                metrics,
+            }))
+        }
+        unknown => Err(Error::Decode(format!(
+            "Unknown FTDC document type: {unknown}"
+        ))),
+    }
+}
+
+/// Decodes the `data` field of a metric chunk: a little endian `u32` with the uncompressed size
+/// followed by the zlib compressed payload. The payload consists of the reference document, the
+/// number of metrics and samples and the varint encoded deltas, stored metric by metric with
+/// runs of zeros compressed into a zero followed by the count of additional zeros.
+fn decode_chunk(data: &[u8]) -> Result<(Document, Vec<Metric>), Error> {
+    if data.len() < 4 {
+        return Err(Error::Decode("FTDC metric chunk is too short".to_string()));
+    }
+    let (length, compressed) = data.split_at(4);
+    let uncompressed_length = u32::from_le_bytes([length[0], length[1], length[2], length[3]]);
+
+    // The sizes in the header are not trusted for allocations, a corrupt file could ask for
+    // gigabytes. Inflating stops one byte after the expected length to detect a mismatch.
+    let mut payload = Vec::new();
+    ZlibDecoder::new(compressed)
+        .take(u64::from(uncompressed_length) + 1)
+        .read_to_end(&mut payload)?;
+    if payload.len() != uncompressed_length as usize {
+        return Err(Error::Decode(format!(
+            "FTDC metric chunk has {actual} bytes after inflating, expected {uncompressed_length}",
+            actual = payload.len()
+        )));
+    }
+
+    let mut payload = payload.as_slice();
+    let reference = Document::from_reader(&mut payload)?;
+    let metric_count = read_u32(&mut payload)? as usize;
+    let delta_count = read_u32(&mut payload)? as usize;
+
+    let mut reference_values = Vec::new();
+    extract_metrics("", &reference, &mut reference_values);
+    if reference_values.len() != metric_count {
+        return Err(Error::Decode(format!(
+            "FTDC reference document contains {actual} metrics, chunk header says {metric_count}",
+            actual = reference_values.len()
+        )));
+    }
+
+    // Runs of zeros do not consume any input, so the counts are checked before decoding.
+    let mut remaining = metric_count as u64 * delta_count as u64;
+    if remaining > MAX_CHUNK_DELTAS {
+        return Err(Error::Decode(format!(
+            "FTDC metric chunk claims {metric_count} metrics with {delta_count} samples each, at most {MAX_CHUNK_DELTAS} deltas are supported"
+        )));
+    }
+
+    let mut zeros = 0u64;
+    let mut metrics = Vec::with_capacity(metric_count);
+    for (path, reference_value) in reference_values {
+        // Every delta takes at least one byte unless it is part of a run of zeros.
+        let mut values = Vec::with_capacity(delta_count.min(payload.len()) + 1);
+        let mut value = reference_value;
+        values.push(value);
+
+        for _ in 0..delta_count {
+            remaining -= 1;
+            let delta = if zeros > 0 {
+                zeros -= 1;
+                0
+            } else {
+                let delta = read_varint(&mut payload)?;
+                if delta == 0 {
+                    zeros = read_varint(&mut payload)?;
+                    if zeros > remaining {
+                        return Err(Error::Decode(format!(
+                            "FTDC metric chunk has a run of {zeros} zeros, but only {remaining} deltas are left"
+                        )));
+                    }
+                }
+                delta
+            };
+            value = value.wrapping_add(delta as i64);
+            values.push(value);
+        }
+
+        metrics.push(Metric { path, values });
+    }
+
+    Ok((reference, metrics))
+}
+
+/// Collects the numeric values of a document in the order mongod does when it compresses a chunk:
+/// numbers, booleans and dates yield one metric, timestamps two (`.t` and `.i`) and nested
+/// documents and arrays are walked depth first. Every other type is skipped.
+fn extract_metrics(prefix: &str, document: &Document, metrics: &mut Vec<(String, i64)>) {
+    for (key, value) in document {
+        extract_value(join(prefix, key), value, metrics);
+    }
+}
+
+fn extract_value(path: String, value: &Bson, metrics: &mut Vec<(String, i64)>) {
+    match value {
+        Bson::Double(v) => metrics.push((path, *v as i64)),
+        Bson::Int32(v) => metrics.push((path, i64::from(*v))),
+        Bson::Int64(v) => metrics.push((path, *v)),
+        Bson::Decimal128(v) => {
+            metrics.push((path, v.to_string().parse::<f64>().map_or(0, |v| v as i64)))
+        }
+        Bson::Boolean(v) => metrics.push((path, i64::from(*v))),
+        Bson::DateTime(v) => metrics.push((path, v.timestamp_millis())),
+        Bson::Timestamp(v) => {
+            metrics.push((join(&path, "t"), i64::from(v.time)));
+            metrics.push((join(&path, "i"), i64::from(v.increment)));
+        }
+        Bson::Document(document) => extract_metrics(&path, document, metrics),
+        Bson::Array(array) => array.iter().enumerate().for_each(|(index, value)| {
+            extract_value(join(&path, &index.to_string()), value, metrics)
+        }),
+        _ => {}
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() { key.to_string() } else { format!("{prefix}.{key}") }
+}
+
+fn read_u32(payload: &mut &[u8]) -> Result<u32, Error> {
+    let mut bytes = [0u8; 4];
+    payload.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_varint(payload: &mut &[u8]) -> Result<u64, Error> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = payload
+            .split_first()
+            .ok_or_else(|| Error::Decode("Unexpected end of FTDC metric chunk".to_string()))?;
+        *payload = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(Error::Decode(
+        "Varint in FTDC metric chunk is too long".to_string(),
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Write;
+
+    use bson::Bson;
+    use bson::DateTime;
+    use bson::Document;
+    use bson::Timestamp;
+    use bson::doc;
+    use bson::spec::BinarySubtype;
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+
+    use crate::decoder::FtdcDocument;
+    use crate::decoder::FtdcReader;
+    use crate::decoder::Metric;
+    use crate::decoder::decode_chunk;
+    use crate::decoder::read_varint;
+
+    fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    /// Encodes a metric chunk the way mongod does, `deltas` holds one row of deltas per metric.
+    pub(crate) fn encode_chunk(reference: &Document, deltas: &[Vec<u64>]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        reference.to_writer(&mut payload).unwrap();
+        payload.extend_from_slice(&(deltas.len() as u32).to_le_bytes());
+        payload.extend_from_slice(&(deltas.first().map_or(0, Vec::len) as u32).to_le_bytes());
+
+        let flat: Vec<u64> = deltas.iter().flatten().copied().collect();
+        let mut i = 0;
+        while i < flat.len() {
+            write_varint(flat[i], &mut payload);
+            if flat[i] == 0 {
+                let mut zeros = 0;
+                while i + 1 < flat.len() && flat[i + 1] == 0 {
+                    zeros += 1;
+                    i += 1;
+                }
+                write_varint(zeros, &mut payload);
+            }
+            i += 1;
+        }
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&payload).unwrap();
+        let mut data = (payload.len() as u32).to_le_bytes().to_vec();
+        data.extend(encoder.finish().unwrap());
+        data
+    }
+
+    /// A metric chunk with the given header counts and encoded deltas, consistent or not.
+    fn raw_chunk(
+        reference: &Document,
+        metric_count: u32,
+        delta_count: u32,
+        deltas: &[u8],
+    ) -> Vec<u8> {
+        let mut payload = Vec::new();
+        reference.to_writer(&mut payload).unwrap();
+        payload.extend_from_slice(&metric_count.to_le_bytes());
+        payload.extend_from_slice(&delta_count.to_le_bytes());
+        payload.extend_from_slice(deltas);
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&payload).unwrap();
+        let mut data = (payload.len() as u32).to_le_bytes().to_vec();
+        data.extend(encoder.finish().unwrap());
+        data
+    }
+
+    pub(crate) fn chunk_document(id: i64, reference: &Document, deltas: &[Vec<u64>]) -> Document {
+        doc! {
+            "_id": DateTime::from_millis(id),
+            "type": 1,
+            "data": Bson::Binary(bson::Binary {
+                subtype: BinarySubtype::Generic,
+                bytes: encode_chunk(reference, deltas),
+            }),
+        }
+    }
+
+    #[test]
+    fn given_multi_byte_varint_when_read_varint_then_decode_value() {
+        // Given
+        let mut bytes: &[u8] = &[0xac, 0x02, 0x01];
+
+        // When
+        let value = read_varint(&mut bytes).unwrap();
+
+        // Then
+        assert_eq!(value, 300);
+        assert_eq!(bytes, &[0x01]);
+    }
+
+    #[test]
+    fn given_encoded_chunk_when_decode_chunk_then_get_metrics_in_document_order() {
+        // Given
+        let reference = doc! {
+            "start": DateTime::from_millis(1_000),
+            "serverStatus": {
+                "host": "not a metric",
+                "uptime": 5,
+                "ok": true,
+                "opcounters": [10_i64, 2.9],
+                "repl": Timestamp { time: 100, increment: 1 },
+            },
+        };
+        let deltas = vec![
+            vec![1_000, 1_000, 1_000],
+            vec![1, 0, 0],
+            vec![0, 0, 0],
+            vec![0, 0, 5],
+            vec![(-1_i64) as u64, 0, 0],
+            vec![1, 1, 1],
+            vec![0, 0, 0],
+        ];
+
+        // When
+        let (_, metrics) = decode_chunk(&encode_chunk(&reference, &deltas)).unwrap();
+
+        // Then
+        let expected =
+            |path: &str, values: &[i64]| Metric { path: path.to_string(), values: values.to_vec() };
+        assert_eq!(
+            metrics,
+            vec![
+                expected("start", &[1_000, 2_000, 3_000, 4_000]),
+                expected("serverStatus.uptime", &[5, 6, 6, 6]),
+                expected("serverStatus.ok", &[1, 1, 1, 1]),
+                expected("serverStatus.opcounters.0", &[10, 10, 10, 15]),
+                expected("serverStatus.opcounters.1", &[2, 1, 1, 1]),
+                expected("serverStatus.repl.t", &[100, 101, 102, 103]),
+                expected("serverStatus.repl.i", &[1, 1, 1, 1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn given_wrong_metric_count_when_decode_chunk_then_decode_error() {
+        // Given
+        let reference = doc! { "a": 1, "b": 2 };
+        let deltas = vec![vec![0, 0]];
+
+        // When
+        let error = decode_chunk(&encode_chunk(&reference, &deltas))
+            .unwrap_err()
+            .to_string();
+
+        // Then
+        assert_eq!(
+            error,
+            "FTDC reference document contains 2 metrics, chunk header says 1"
+        );
+    }
+
+    #[test]
+    fn given_corrupt_chunk_header_when_decode_chunk_then_decode_error_without_allocating() {
+        // Given
+        let reference = doc! { "a": 1 };
+        let mut data = encode_chunk(&reference, &[vec![1]]);
+        data[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let truncated = raw_chunk(&reference, 1, u32::MAX, &[]);
+
+        // When
+        let wrong_length = decode_chunk(&data).unwrap_err().to_string();
+        let wrong_delta_count = decode_chunk(&truncated).unwrap_err().to_string();
+
+        // Then
+        assert!(
+            wrong_length.ends_with("expected 4294967295"),
+            "{wrong_length}"
+        );
+        assert!(
+            wrong_delta_count.starts_with("FTDC metric chunk claims 1 metrics with 4294967295"),
+            "{wrong_delta_count}"
+        );
+    }
+
+    #[test]
+    fn given_zero_run_beyond_the_deltas_when_decode_chunk_then_decode_error() {
+        // Given
+        let reference = doc! { "a": 1, "b": 2 };
+        let mut deltas = Vec::new();
+        write_varint(0, &mut deltas);
+        write_varint(u64::MAX, &mut deltas);
+        let chunk = raw_chunk(&reference, 2, 1_000, &deltas);
+
+        // When
+        let error = decode_chunk(&chunk).unwrap_err().to_string();
+
+        // Then
+        assert_eq!(
+            error,
+            "FTDC metric chunk has a run of 18446744073709551615 zeros, but only 1999 deltas are left"
+        );
+    }
+
+    #[test]
+    fn given_document_length_beyond_bson_maximum_when_read_then_decode_error() {
+        // Given
+        let file = i32::MAX.to_le_bytes();
+
+        // When
+        let error = FtdcReader::new(file.as_slice())
+            .next()
+            .unwrap()
+            .unwrap_err()
+            .to_string();
+
+        // Then
+        assert_eq!(error, "Invalid BSON document length: 2147483647");
+    }
+
+    #[test]
+    fn given_metrics_file_when_read_then_get_metadata_and_chunks() {
+        // Given
+        let metadata = doc! {
+            "_id": DateTime::from_millis(1),
+            "type": 0,
+            "doc": { "hostInfo": { "system": { "hostname": "host-00:27017" } } },
+        };
+        let chunk = chunk_document(
+            2,
+            &doc! { "start": DateTime::from_millis(10) },
+            &[vec![5, 5]],
+        );
+        let mut file = Vec::new();
+        metadata.to_writer(&mut file).unwrap();
+        chunk.to_writer(&mut file).unwrap();
+
+        // When
+        let documents = FtdcReader::new(file.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        // Then
+        assert_eq!(documents.len(), 2);
+        assert!(matches!(&documents[0], FtdcDocument::Metadata(m) if m.id.timestamp_millis() == 1));
+        match &documents[1] {
+            FtdcDocument::MetricChunk(chunk) => {
+                assert_eq!(chunk.sample_count(), 3);
+                assert_eq!(chunk.metric("start").unwrap().values, vec![10, 15, 20]);
+            }
+            other => panic!("Expected a metric chunk, got {other:?}"),
+        }
+    }
+}
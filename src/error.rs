@@ -2,37 +2,240 @@ use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result;
 
+use reqwest::Response;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
 #[derive(Debug)]
 pub enum Error {
     Diqwest(diqwest::error::Error),
     Reqwest(reqwest::Error),
     Json(serde_json::Error),
+    Bson(bson::de::Error),
     Io(std::io::Error),
+    #[cfg(feature = "indicatif")]
     IndicatifTemplate(indicatif::style::TemplateError),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet::errors::ParquetError),
     InvalidJobState(String),
-    Download(String),
-    CheckJobStatus(String),
-    CreateJob(String),
+    InvalidLogType(String),
+    InvalidDate(String),
+    Download(Box<ApiError>),
+    CheckJobStatus(Box<ApiError>),
+    CreateJob(Box<ApiError>),
+    ListJobs(Box<ApiError>),
+    DeleteJob(Box<ApiError>),
+    ExtendJob(Box<ApiError>),
+    RetryJob(Box<ApiError>),
+    ListProcesses(Box<ApiError>),
+    GetClusters(Box<ApiError>),
+    JobFailed(String),
+    JobExpired(String),
     ReplicaSetNotFound(String),
+    AmbiguousReplicaSet(String),
     MongoJob(String),
+    Authentication(Box<ApiError>),
+    JobTimeout(String),
+    Cancelled(String),
+    Decode(String),
+    OutputExists(String),
+    InvalidTemplate(String),
+    MissingCredentials(String),
+    InvalidApiFlavor(String),
+    MissingBaseUrl(String),
+    UnsupportedByFlavor(String),
+    UnsafeArchiveEntry(String),
+    InvalidExport(String),
+}
+
+/// The error body of the Atlas Admin API, e.g. `{"error": 404, "errorCode":
+/// "RESOURCE_NOT_FOUND", "reason": "Not Found", "detail": "..."}`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtlasErrorBody {
+    pub error: Option<u16>,
+    pub error_code: Option<String>,
+    pub reason: Option<String>,
+    pub detail: Option<String>,
+}
+
+/// A request the Atlas Admin API answered with an unexpected status.
+#[derive(Debug)]
+pub struct ApiError {
+    /// What went wrong, e.g. `Something went wrong creating the FTDC job`.
+    pub message: String,
+    pub status: StatusCode,
+    /// The path of the request, e.g. `/api/atlas/v2/groups/{group}/logCollectionJobs`.
+    pub endpoint: String,
+    /// The job the request was about, if any.
+    pub job_id: Option<String>,
+    /// The parsed error body, `None` if the body is not an Atlas error.
+    pub atlas_error: Option<AtlasErrorBody>,
+    pub body: String,
+}
+
+impl ApiError {
+    /// Reads the body of a response with an unexpected status.
+    pub(crate) async fn from_response(
+        response: Response,
+        message: impl Into<String>,
+        job_id: Option<&str>,
+    ) -> std::result::Result<Box<Self>, Error> {
+        let status = response.status();
+        let endpoint = response.url().path().to_string();
+        let body = response.text().await?;
+
+        Ok(Box::new(Self {
+            message: message.into(),
+            status,
+            endpoint,
+            job_id: job_id.map(str::to_string),
+            atlas_error: serde_json::from_str(&body).ok(),
+            body,
+        }))
+    }
+
+    /// The Atlas error code, e.g. `RESOURCE_NOT_FOUND`.
+    pub fn error_code(&self) -> Option<&str> {
+        self.atlas_error.as_ref()?.error_code.as_deref()
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}. Status code: {}.", self.message, self.status)?;
+        match &self.atlas_error {
+            Some(atlas_error) => write!(
+                f,
+                " Atlas error {code}: {detail}",
+                code = atlas_error.error_code.as_deref().unwrap_or("-"),
+                detail = atlas_error
+                    .detail
+                    .as_deref()
+                    .or(atlas_error.reason.as_deref())
+                    .unwrap_or("-")
+            ),
+            None if !self.body.is_empty() => write!(f, " Body: {}", self.body),
+            None => Ok(()),
+        }
+    }
 }
 
-impl std::error::Error for Error {}
+impl Error {
+    /// The request Atlas answered with an unexpected status, if that is what went wrong.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            Error::Download(e)
+            | Error::CheckJobStatus(e)
+            | Error::CreateJob(e)
+            | Error::ListJobs(e)
+            | Error::DeleteJob(e)
+            | Error::ExtendJob(e)
+            | Error::RetryJob(e)
+            | Error::ListProcesses(e)
+            | Error::GetClusters(e)
+            | Error::Authentication(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// The error of the HTTP client, also if it failed during digest authentication.
+    fn request_error(&self) -> Option<&reqwest::Error> {
+        match self {
+            Error::Reqwest(e) | Error::Diqwest(diqwest::error::Error::Reqwest(e)) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status Atlas answered with, if the error is about an unexpected status.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.api_error().map(|e| e.status)
+    }
+
+    /// Whether the credentials were rejected or lack the permissions for the request.
+    pub fn is_auth_error(&self) -> bool {
+        matches!(self, Error::Authentication(_))
+            || matches!(
+                self.status(),
+                Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+            )
+    }
+
+    /// Whether trying again later may succeed: Atlas rate limited the request or failed
+    /// temporarily, the connection failed or dropped, or a job failed or did not finish in time.
+    pub fn is_retryable(&self) -> bool {
+        if let Some(e) = self.request_error() {
+            return e.is_connect() || e.is_timeout() || e.is_request() || e.is_body();
+        }
+        match self {
+            Error::JobFailed(_) | Error::JobTimeout(_) => true,
+            _ => self.status().is_some_and(|status| {
+                status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+            }),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Diqwest(e) => Some(e),
+            Error::Reqwest(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::Bson(e) => Some(e),
+            Error::Io(e) => Some(e),
+            #[cfg(feature = "indicatif")]
+            Error::IndicatifTemplate(e) => Some(e),
+            #[cfg(feature = "parquet")]
+            Error::Parquet(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
-            Error::Diqwest(e) => std::fmt::Display::fmt(e, f),
-            Error::Reqwest(e) => std::fmt::Display::fmt(e, f),
-            Error::Json(e) => std::fmt::Display::fmt(e, f),
-            Error::Io(e) => std::fmt::Display::fmt(e, f),
+            Error::Diqwest(e) => write!(f, "Digest authentication against Atlas failed: {e}"),
+            Error::Reqwest(e) => write!(f, "Request to Atlas failed: {e}"),
+            Error::Json(e) => write!(f, "Invalid JSON: {e}"),
+            Error::Bson(e) => write!(f, "Invalid BSON: {e}"),
+            Error::Io(e) => write!(f, "I/O error: {e}"),
             Error::InvalidJobState(e) => std::fmt::Display::fmt(e, f),
+            Error::InvalidLogType(e) => std::fmt::Display::fmt(e, f),
+            Error::InvalidDate(e) => std::fmt::Display::fmt(e, f),
             Error::Download(e) => std::fmt::Display::fmt(e, f),
             Error::CheckJobStatus(e) => std::fmt::Display::fmt(e, f),
             Error::CreateJob(e) => std::fmt::Display::fmt(e, f),
+            Error::ListJobs(e) => std::fmt::Display::fmt(e, f),
+            Error::DeleteJob(e) => std::fmt::Display::fmt(e, f),
+            Error::ExtendJob(e) => std::fmt::Display::fmt(e, f),
+            Error::RetryJob(e) => std::fmt::Display::fmt(e, f),
+            Error::ListProcesses(e) => std::fmt::Display::fmt(e, f),
+            Error::GetClusters(e) => std::fmt::Display::fmt(e, f),
+            Error::JobFailed(e) => std::fmt::Display::fmt(e, f),
+            Error::JobExpired(e) => std::fmt::Display::fmt(e, f),
             Error::ReplicaSetNotFound(e) => std::fmt::Display::fmt(e, f),
+            Error::AmbiguousReplicaSet(e) => std::fmt::Display::fmt(e, f),
             Error::MongoJob(e) => std::fmt::Display::fmt(e, f),
-            Error::IndicatifTemplate(e) => std::fmt::Display::fmt(e, f),
+            Error::Authentication(e) => std::fmt::Display::fmt(e, f),
+            Error::JobTimeout(e) => std::fmt::Display::fmt(e, f),
+            Error::Cancelled(e) => std::fmt::Display::fmt(e, f),
+            #[cfg(feature = "indicatif")]
+            Error::IndicatifTemplate(e) => write!(f, "Invalid progress bar template: {e}"),
+            #[cfg(feature = "parquet")]
+            Error::Parquet(e) => write!(f, "Writing Parquet failed: {e}"),
+            Error::Decode(e) => std::fmt::Display::fmt(e, f),
+            Error::OutputExists(e) => std::fmt::Display::fmt(e, f),
+            Error::InvalidTemplate(e) => std::fmt::Display::fmt(e, f),
+            Error::MissingCredentials(e) => std::fmt::Display::fmt(e, f),
+            Error::InvalidApiFlavor(e) => std::fmt::Display::fmt(e, f),
+            Error::MissingBaseUrl(e) => std::fmt::Display::fmt(e, f),
+            Error::UnsupportedByFlavor(e) => std::fmt::Display::fmt(e, f),
+            Error::UnsafeArchiveEntry(e) => std::fmt::Display::fmt(e, f),
+            Error::InvalidExport(e) => std::fmt::Display::fmt(e, f),
         }
     }
 }
@@ -55,14 +258,102 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+impl From<bson::de::Error> for Error {
+    fn from(bson_error: bson::de::Error) -> Self {
+        Error::Bson(bson_error)
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(io_error: std::io::Error) -> Self {
         Error::Io(io_error)
     }
 }
 
+#[cfg(feature = "indicatif")]
 impl From<indicatif::style::TemplateError> for Error {
     fn from(template_error: indicatif::style::TemplateError) -> Self {
         Error::IndicatifTemplate(template_error)
     }
 }
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(parquet_error: parquet::errors::ParquetError) -> Self {
+        Error::Parquet(parquet_error)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<arrow_schema::ArrowError> for Error {
+    fn from(arrow_error: arrow_schema::ArrowError) -> Self {
+        Error::Parquet(arrow_error.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use reqwest::StatusCode;
+
+    use crate::error::ApiError;
+    use crate::error::Error;
+
+    fn api_error(status: StatusCode) -> Box<ApiError> {
+        Box::new(ApiError {
+            message: "Something went wrong creating the FTDC job".to_string(),
+            status,
+            endpoint: "/api/atlas/v2/groups/my-group-key/logCollectionJobs".to_string(),
+            job_id: None,
+            atlas_error: None,
+            body: String::new(),
+        })
+    }
+
+    #[test]
+    fn given_statuses_when_classify_errors_then_tell_auth_from_retryable_errors() {
+        // Given
+        let forbidden = Error::CreateJob(api_error(StatusCode::FORBIDDEN));
+        let rate_limited = Error::CreateJob(api_error(StatusCode::TOO_MANY_REQUESTS));
+        let unavailable = Error::ListJobs(api_error(StatusCode::SERVICE_UNAVAILABLE));
+        let bad_request = Error::CreateJob(api_error(StatusCode::BAD_REQUEST));
+
+        // When
+        let auth_errors =
+            [&forbidden, &rate_limited, &unavailable, &bad_request].map(Error::is_auth_error);
+        let retryable =
+            [&forbidden, &rate_limited, &unavailable, &bad_request].map(Error::is_retryable);
+
+        // Then
+        assert_eq!(auth_errors, [true, false, false, false]);
+        assert_eq!(retryable, [false, true, true, false]);
+    }
+
+    #[tokio::test]
+    async fn given_connection_failure_under_digest_auth_when_is_retryable_then_retryable() {
+        // Given
+        let refused = reqwest::get("http://127.0.0.1:1").await.unwrap_err();
+        let error = Error::Diqwest(diqwest::error::Error::Reqwest(refused));
+
+        // When
+        let retryable = error.is_retryable();
+
+        // Then
+        assert!(retryable);
+    }
+
+    #[test]
+    fn given_wrapped_error_when_display_and_source_then_show_the_wrapped_error() {
+        // Given
+        let error = Error::from(io::Error::other("disk full"));
+
+        // When
+        let source = error.source().map(ToString::to_string);
+
+        // Then
+        assert_eq!(error.to_string(), "I/O error: disk full");
+        assert_eq!(source.as_deref(), Some("disk full"));
+    }
+}
@@ -0,0 +1,495 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::str::FromStr;
+
+use bson::DateTime;
+use regex::Regex;
+
+use crate::dataset::FtdcDataset;
+use crate::dataset::MetricSeries;
+use crate::error::Error;
+
+/// How the samples are laid out in the exported table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// One row per host and timestamp with a column per metric. Metrics a host did not report at
+    /// a timestamp are left empty.
+    #[default]
+    Wide,
+    /// One row per host, metric and timestamp with the columns `timestamp`, `host`, `metric` and
+    /// `value`.
+    Long,
+}
+
+impl FromStr for Layout {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.to_lowercase().as_str() {
+            "wide" => Ok(Layout::Wide),
+            "long" => Ok(Layout::Long),
+            _ => Err(Error::InvalidExport(format!(
+                "Unknown layout {s}. Use wide or long."
+            ))),
+        }
+    }
+}
+
+/// The file format to export to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma separated values with a header row. Timestamps are written as RFC 3339.
+    #[default]
+    Csv,
+    /// Apache Parquet with timestamps in milliseconds (UTC). Needs the `parquet` feature.
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            #[cfg(feature = "parquet")]
+            "parquet" => Ok(ExportFormat::Parquet),
+            #[cfg(not(feature = "parquet"))]
+            "parquet" => Err(Error::InvalidExport(
+                "Exporting to Parquet needs the `parquet` feature.".to_string(),
+            )),
+            _ => Err(Error::InvalidExport(format!(
+                "Unknown export format {s}. Use csv or parquet."
+            ))),
+        }
+    }
+}
+
+/// Selects metrics by their dotted path. A metric is selected if any of the patterns matches,
+/// every metric if there are none.
+#[derive(Debug, Clone, Default)]
+pub struct MetricFilter {
+    patterns: Vec<Regex>,
+}
+
+impl MetricFilter {
+    /// Adds a glob pattern. `*` matches within a path segment, `**` across segments and `?` a
+    /// single character, e.g. `serverStatus.connections.*` or `**.bytes read`.
+    pub fn with_glob(self, glob: &str) -> Result<Self, Error> {
+        let mut pattern = String::from("^");
+        let mut chars = glob.chars().peekable();
+        while let Some(char) = chars.next() {
+            match char {
+                '*' if chars.next_if_eq(&'*').is_some() => pattern.push_str(".*"),
+                '*' => pattern.push_str("[^.]*"),
+                '?' => pattern.push_str("[^.]"),
+                _ => pattern.push_str(&regex::escape(&char.to_string())),
+            }
+        }
+        pattern.push('$');
+        self.with_regex(&pattern)
+    }
+
+    /// Adds a regular expression that has to match a part of the path, e.g.
+    /// `^serverStatus\.opcounters\.`.
+    pub fn with_regex(mut self, regex: &str) -> Result<Self, Error> {
+        let regex = Regex::new(regex)
+            .map_err(|e| Error::InvalidExport(format!("Invalid metric pattern {regex}: {e}")))?;
+        self.patterns.push(regex);
+        Ok(self)
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        self.patterns.is_empty() || self.patterns.iter().any(|regex| regex.is_match(path))
+    }
+}
+
+/// What and how to export.
+#[derive(Debug, Clone, Default)]
+pub struct ExportConfig {
+    pub filter: MetricFilter,
+    pub layout: Layout,
+    pub format: ExportFormat,
+}
+
+impl ExportConfig {
+    pub fn with_filter(self, filter: MetricFilter) -> Self {
+        Self { filter, ..self }
+    }
+
+    pub fn with_layout(self, layout: Layout) -> Self {
+        Self { layout, ..self }
+    }
+
+    pub fn with_format(self, format: ExportFormat) -> Self {
+        Self { format, ..self }
+    }
+}
+
+/// The selected samples, row by row.
+#[derive(Debug, Default, PartialEq)]
+struct Table {
+    timestamps: Vec<DateTime>,
+    hosts: Vec<String>,
+    values: Values,
+}
+
+#[derive(Debug, PartialEq)]
+enum Values {
+    /// A column of values per metric.
+    Wide(Vec<(String, Vec<Option<i64>>)>),
+    /// The metric and value of every row.
+    Long {
+        metrics: Vec<String>,
+        values: Vec<i64>,
+    },
+}
+
+impl Default for Values {
+    fn default() -> Self {
+        Values::Long { metrics: Vec::new(), values: Vec::new() }
+    }
+}
+
+/// Writes the metrics of `dataset` selected by the filter of `config` to `writer` and returns the
+/// number of rows written. Rows are ordered by host and timestamp, in the long layout by host,
+/// metric and timestamp.
+pub fn export(
+    dataset: &FtdcDataset,
+    config: &ExportConfig,
+    writer: impl Write + Send,
+) -> Result<usize, Error> {
+    let series: Vec<&MetricSeries> = dataset
+        .iter()
+        .filter(|series| config.filter.matches(&series.path))
+        .collect();
+    let table = match config.layout {
+        Layout::Wide => wide_table(&series),
+        Layout::Long => long_table(&series),
+    };
+
+    match config.format {
+        ExportFormat::Csv => write_csv(&table, writer)?,
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => parquet_writer::write_parquet(&table, writer)?,
+    }
+    Ok(table.timestamps.len())
+}
+
+fn wide_table(series: &[&MetricSeries]) -> Table {
+    let metrics: BTreeSet<&str> = series.iter().map(|series| series.path.as_str()).collect();
+    let column: BTreeMap<&str, usize> = metrics.iter().enumerate().map(|(i, m)| (*m, i)).collect();
+
+    let mut rows: BTreeMap<(&str, DateTime), Vec<Option<i64>>> = BTreeMap::new();
+    for series in series {
+        for (timestamp, value) in series.samples() {
+            let row = rows
+                .entry((series.host.as_str(), timestamp))
+                .or_insert_with(|| vec![None; metrics.len()]);
+            row[column[series.path.as_str()]] = Some(value);
+        }
+    }
+
+    let mut table = Table::default();
+    let mut columns = vec![Vec::with_capacity(rows.len()); metrics.len()];
+    for ((host, timestamp), row) in rows {
+        table.timestamps.push(timestamp);
+        table.hosts.push(host.to_string());
+        columns
+            .iter_mut()
+            .zip(row)
+            .for_each(|(column, value)| column.push(value));
+    }
+    table.values = Values::Wide(
+        metrics
+            .into_iter()
+            .map(str::to_string)
+            .zip(columns)
+            .collect(),
+    );
+    table
+}
+
+fn long_table(series: &[&MetricSeries]) -> Table {
+    let mut table = Table::default();
+    let mut metrics = Vec::new();
+    let mut values = Vec::new();
+    for series in series {
+        for (timestamp, value) in series.samples() {
+            table.timestamps.push(timestamp);
+            table.hosts.push(series.host.clone());
+            metrics.push(series.path.clone());
+            values.push(value);
+        }
+    }
+    table.values = Values::Long { metrics, values };
+    table
+}
+
+fn write_csv(table: &Table, mut writer: impl Write) -> Result<(), Error> {
+    let mut header = vec!["timestamp", "host"];
+    match &table.values {
+        Values::Wide(columns) => header.extend(columns.iter().map(|(metric, _)| metric.as_str())),
+        Values::Long { .. } => header.extend(["metric", "value"]),
+    }
+    write_csv_record(&mut writer, header)?;
+
+    for (row, timestamp) in table.timestamps.iter().enumerate() {
+        let mut record = vec![rfc3339(*timestamp), table.hosts[row].clone()];
+        match &table.values {
+            Values::Wide(columns) => {
+                record.extend(columns.iter().map(|(_, values)| {
+                    values[row].map_or(String::new(), |value| value.to_string())
+                }))
+            }
+            Values::Long { metrics, values } => {
+                record.extend([metrics[row].clone(), values[row].to_string()])
+            }
+        }
+        write_csv_record(&mut writer, record.iter().map(String::as_str))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Metric paths may contain spaces and other characters, fields are quoted where needed.
+fn write_csv_record<'a>(
+    writer: &mut impl Write,
+    fields: impl IntoIterator<Item = &'a str>,
+) -> Result<(), Error> {
+    let record: Vec<String> = fields
+        .into_iter()
+        .map(|field| match field.contains([',', '"', '\n', '\r']) {
+            true => format!("\"{}\"", field.replace('"', "\"\"")),
+            false => field.to_string(),
+        })
+        .collect();
+    writeln!(writer, "{}", record.join(","))?;
+    Ok(())
+}
+
+fn rfc3339(timestamp: DateTime) -> String {
+    timestamp
+        .try_to_rfc3339_string()
+        .unwrap_or_else(|_| timestamp.timestamp_millis().to_string())
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_writer {
+    use std::io::Write;
+    use std::sync::Arc;
+
+    use arrow_array::ArrayRef;
+    use arrow_array::Int64Array;
+    use arrow_array::RecordBatch;
+    use arrow_array::StringArray;
+    use arrow_array::TimestampMillisecondArray;
+    use arrow_schema::DataType;
+    use arrow_schema::Field;
+    use arrow_schema::Schema;
+    use arrow_schema::TimeUnit;
+    use parquet::arrow::ArrowWriter;
+
+    use crate::error::Error;
+    use crate::export::Table;
+    use crate::export::Values;
+
+    pub(super) fn write_parquet(table: &Table, writer: impl Write + Send) -> Result<(), Error> {
+        let mut fields = vec![
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+                false,
+            ),
+            Field::new("host", DataType::Utf8, false),
+        ];
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    table.timestamps.iter().map(|t| t.timestamp_millis()),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(StringArray::from_iter_values(&table.hosts)),
+        ];
+        match &table.values {
+            Values::Wide(metrics) => {
+                for (metric, values) in metrics {
+                    fields.push(Field::new(metric, DataType::Int64, true));
+                    columns.push(Arc::new(Int64Array::from(values.clone())));
+                }
+            }
+            Values::Long { metrics, values } => {
+                fields.push(Field::new("metric", DataType::Utf8, false));
+                fields.push(Field::new("value", DataType::Int64, false));
+                columns.push(Arc::new(StringArray::from_iter_values(metrics)));
+                columns.push(Arc::new(Int64Array::from(values.clone())));
+            }
+        }
+
+        let schema = Arc::new(Schema::new(fields));
+        let batch = RecordBatch::try_new(schema.clone(), columns)?;
+        let mut writer = ArrowWriter::try_new(writer, schema, None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use bson::DateTime;
+    use bson::Document;
+    use bson::doc;
+
+    use crate::dataset::FtdcDataset;
+    use crate::decoder::FtdcDocument;
+    use crate::decoder::FtdcReader;
+    use crate::decoder::tests::chunk_document;
+    use crate::error::Error;
+    use crate::export::ExportConfig;
+    use crate::export::ExportFormat;
+    use crate::export::Layout;
+    use crate::export::MetricFilter;
+    use crate::export::export;
+
+    /// A dataset of one host sampling `start`, `serverStatus.connections.current` and
+    /// `serverStatus.connections.available`, a row of `deltas` per metric.
+    pub(crate) fn dataset(host: &str, start: i64, deltas: &[Vec<u64>]) -> FtdcDataset {
+        let reference = doc! {
+            "start": DateTime::from_millis(start),
+            "serverStatus": { "connections": { "current": 5, "available": 100 } },
+        };
+        dataset_of(host, start, &reference, deltas)
+    }
+
+    pub(crate) fn dataset_of(
+        host: &str,
+        start: i64,
+        reference: &Document,
+        deltas: &[Vec<u64>],
+    ) -> FtdcDataset {
+        let mut file = Vec::new();
+        chunk_document(start, reference, deltas)
+            .to_writer(&mut file)
+            .unwrap();
+        let documents: Vec<FtdcDocument> = FtdcReader::new(file.as_slice())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        FtdcDataset::from_documents(host, documents).unwrap()
+    }
+
+    #[test]
+    fn given_globs_and_regexes_when_matches_then_select_metrics_by_dotted_path() {
+        // Given
+        let filter = MetricFilter::default()
+            .with_glob("serverStatus.connections.*")
+            .unwrap()
+            .with_regex(r"cache\.bytes")
+            .unwrap();
+        let deep = MetricFilter::default().with_glob("**.current").unwrap();
+
+        // When
+        let paths = [
+            "serverStatus.connections.current",
+            "serverStatus.connections.active.total",
+            "serverStatus.wiredTiger.cache.bytes read into cache",
+        ];
+        let matches = paths.map(|path| filter.matches(path));
+        let deep_matches = paths.map(|path| deep.matches(path));
+
+        // Then
+        assert_eq!(matches, [true, false, true]);
+        assert_eq!(deep_matches, [true, false, false]);
+        assert!(matches!(
+            MetricFilter::default().with_regex("("),
+            Err(Error::InvalidExport(_))
+        ));
+    }
+
+    #[test]
+    fn given_dataset_when_export_wide_csv_then_write_a_row_per_host_and_timestamp() {
+        // Given
+        let dataset = dataset(
+            "host-00_27017",
+            1_706_697_900_000,
+            &[vec![1_000], vec![2], vec![0]],
+        );
+        let config = ExportConfig::default().with_filter(
+            MetricFilter::default()
+                .with_glob("serverStatus.connections.*")
+                .unwrap(),
+        );
+        let mut csv = Vec::new();
+
+        // When
+        let rows = export(&dataset, &config, &mut csv).unwrap();
+
+        // Then
+        assert_eq!(rows, 2);
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "timestamp,host,serverStatus.connections.available,serverStatus.connections.current\n\
+             2024-01-31T10:45:00Z,host-00_27017,100,5\n\
+             2024-01-31T10:45:01Z,host-00_27017,100,7\n"
+        );
+    }
+
+    #[test]
+    fn given_dataset_when_export_long_csv_then_write_a_row_per_sample_and_metric() {
+        // Given
+        let dataset = dataset(
+            "host-00_27017",
+            1_706_697_900_000,
+            &[vec![1_000], vec![2], vec![0]],
+        );
+        let config = ExportConfig::default()
+            .with_layout(Layout::Long)
+            .with_format(ExportFormat::Csv)
+            .with_filter(MetricFilter::default().with_glob("**.current").unwrap());
+        let mut csv = Vec::new();
+
+        // When
+        let rows = export(&dataset, &config, &mut csv).unwrap();
+
+        // Then
+        assert_eq!(rows, 2);
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "timestamp,host,metric,value\n\
+             2024-01-31T10:45:00Z,host-00_27017,serverStatus.connections.current,5\n\
+             2024-01-31T10:45:01Z,host-00_27017,serverStatus.connections.current,7\n"
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn given_dataset_when_export_parquet_then_write_readable_parquet_file() {
+        use std::fs::File;
+
+        use parquet::file::reader::FileReader;
+        use parquet::file::serialized_reader::SerializedFileReader;
+        use tempfile::tempdir;
+
+        // Given
+        let dataset = dataset(
+            "host-00_27017",
+            1_706_697_900_000,
+            &[vec![1_000], vec![2], vec![0]],
+        );
+        let config = ExportConfig::default().with_format(ExportFormat::Parquet);
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("export_test.parquet");
+
+        // When
+        let rows = export(&dataset, &config, File::create(&path).unwrap()).unwrap();
+
+        // Then
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(rows, 2);
+        assert_eq!(metadata.num_rows(), 2);
+        assert_eq!(metadata.schema_descr().num_columns(), 2 + 3);
+    }
+}
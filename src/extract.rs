@@ -0,0 +1,300 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use bson::DateTime;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::archive;
+use crate::dataset::FtdcDataset;
+use crate::decoder;
+use crate::decoder::FtdcReader;
+use crate::error::Error;
+use crate::model::LogType;
+
+/// The file the manifest is written to, next to the host directories of the replica set.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+const DIAGNOSTIC_DATA: &str = "diagnostic.data";
+
+/// An extracted file together with the time range of its samples.
+type ExtractedFile = (FileManifest, Option<(DateTime, DateTime)>);
+
+/// What was extracted from a job archive, written to `manifest.json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub replica_set: String,
+    /// The directory the archive was extracted to, `<out>/<replica set>`.
+    #[serde(skip)]
+    pub directory: PathBuf,
+    pub hosts: Vec<HostManifest>,
+}
+
+/// The files of one host, `start` and `end` being the time range of all its FTDC samples.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostManifest {
+    /// The host as `hostname:port`.
+    pub host: String,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub files: Vec<FileManifest>,
+}
+
+/// An extracted file. `start` and `end` are the timestamps (RFC 3339) of its first and last FTDC
+/// sample, `None` for other files or metrics files that cannot be decoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileManifest {
+    /// The path relative to the directory of the replica set, e.g.
+    /// `host-00:27017/diagnostic.data/metrics.interim`.
+    pub path: PathBuf,
+    pub size: u64,
+    pub start: Option<String>,
+    pub end: Option<String>,
+}
+
+/// Extracts the `*.tar.gz` written by `download_ftdc_data`, including the per host archives
+/// nested in it, into `<out_dir>/<replica set>/<host:port>/diagnostic.data/` and writes a
+/// `manifest.json` next to the host directories. Files other than FTDC metrics, e.g. logs, are
+/// extracted into the directory of their host, keeping their path within the host archive.
+///
+/// Without a `replica_set`, the replica set is taken from the default archive name
+/// `{log_type}_data_{replica_set}_job_{job_id}.tar.gz`, or else the archive name. Existing files
+/// are overwritten. Entries pointing outside of the archive fail with
+/// [`Error::UnsafeArchiveEntry`].
+pub fn extract_archive(
+    archive: impl AsRef<Path>,
+    out_dir: impl AsRef<Path>,
+    replica_set: Option<&str>,
+) -> Result<Manifest, Error> {
+    let archive = archive.as_ref();
+    let replica_set = replica_set.map_or_else(|| replica_set_and_job_of(archive).0, str::to_string);
+    let directory = out_dir.as_ref().join(&replica_set);
+
+    let mut hosts: BTreeMap<String, Vec<ExtractedFile>> = BTreeMap::new();
+    archive::visit_files(archive, |host, host_path, _, content| {
+        let host = host_and_port(host);
+        let file_name = host_path.file_name().unwrap_or_default();
+        let is_metrics_file = file_name.to_str().is_some_and(decoder::is_metrics_file);
+        let relative_path = match is_metrics_file {
+            true => Path::new(&host).join(DIAGNOSTIC_DATA).join(file_name),
+            false => Path::new(&host).join(host_path),
+        };
+
+        let path = directory.join(&relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let size = io::copy(content, &mut File::create(&path)?)?;
+        let time_range = match is_metrics_file {
+            true => time_range(&host, &path)?,
+            false => None,
+        };
+
+        let file = FileManifest {
+            path: relative_path,
+            size,
+            start: rfc3339(time_range.map(|(start, _)| start)),
+            end: rfc3339(time_range.map(|(_, end)| end)),
+        };
+        hosts.entry(host).or_default().push((file, time_range));
+        Ok(())
+    })?;
+
+    let manifest = Manifest {
+        replica_set,
+        directory,
+        hosts: hosts
+            .into_iter()
+            .map(|(host, mut files)| {
+                files.sort_by(|(a, _), (b, _)| a.path.cmp(&b.path));
+                let ranges = files.iter().filter_map(|(_, range)| *range);
+                let start = ranges.clone().map(|(start, _)| start).min();
+                let end = ranges.map(|(_, end)| end).max();
+                HostManifest {
+                    host,
+                    start: rfc3339(start),
+                    end: rfc3339(end),
+                    files: files.into_iter().map(|(file, _)| file).collect(),
+                }
+            })
+            .collect(),
+    };
+
+    fs::create_dir_all(&manifest.directory)?;
+    let manifest_file = File::create(manifest.directory.join(MANIFEST_FILE_NAME))?;
+    serde_json::to_writer_pretty(manifest_file, &manifest)?;
+    Ok(manifest)
+}
+
+/// The time range of the samples of a metrics file. A metrics file that is still written to, like
+/// `metrics.interim`, may end in a partial document, so decoding stops at the first error.
+fn time_range(host: &str, path: &Path) -> Result<Option<(DateTime, DateTime)>, Error> {
+    let bytes = fs::read(path)?;
+    let documents = FtdcReader::new(bytes.as_slice()).map_while(Result::ok);
+    Ok(FtdcDataset::from_documents(host, documents)
+        .ok()
+        .and_then(|dataset| dataset.time_range()))
+}
+
+fn rfc3339(date: Option<DateTime>) -> Option<String> {
+    date.and_then(|date| date.try_to_rfc3339_string().ok())
+}
+
+/// The replica set and job id of an archive named after the default file name template, e.g.
+/// `ftdc_data_my-replica-set_job_65b9.tar.gz` or `mongodb_data_...`, or else the archive name
+/// without extension and no job id.
+pub(crate) fn replica_set_and_job_of(archive: &Path) -> (String, Option<String>) {
+    let file_name = archive
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let name = archive::strip_archive_extension(file_name).unwrap_or(file_name);
+    let name = name.strip_prefix("unredacted_").unwrap_or(name);
+
+    let is_log_type = |log_types: &str| {
+        log_types
+            .split('+')
+            .all(|log_type| LogType::from_str(log_type).is_ok())
+    };
+    match name
+        .split_once("_data_")
+        .filter(|(log_types, _)| is_log_type(log_types))
+        .and_then(|(_, name)| name.rsplit_once("_job_"))
+    {
+        Some((replica_set, job_id)) => (replica_set.to_string(), Some(job_id.to_string())),
+        None => (name.to_string(), None),
+    }
+}
+
+/// The per host archives are named `<hostname>_<port>`.
+pub(crate) fn host_and_port(host: &str) -> String {
+    match host.rsplit_once('_') {
+        Some((hostname, port))
+            if !hostname.is_empty()
+                && !port.is_empty()
+                && port.bytes().all(|byte| byte.is_ascii_digit()) =>
+        {
+            format!("{hostname}:{port}")
+        }
+        _ => host.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use tempfile::tempdir;
+
+    use crate::archive::tests::tar_gz;
+    use crate::dataset::tests::metrics_file;
+    use crate::extract::Manifest;
+    use crate::extract::extract_archive;
+    use crate::extract::host_and_port;
+    use crate::extract::replica_set_and_job_of;
+
+    #[test]
+    fn given_job_archive_with_nested_host_archives_when_extract_then_write_layout_and_manifest() {
+        // Given
+        let host_00 = tar_gz(&[
+            (
+                "diagnostic.data/metrics.2024",
+                &metrics_file(1_706_697_900_000, 1),
+            ),
+            (
+                "diagnostic.data/metrics.interim",
+                &metrics_file(1_706_698_000_000, 1),
+            ),
+        ]);
+        let host_01 = tar_gz(&[
+            ("mongodb.log", b"log line"),
+            ("audit/mongodb.log", b"audit line"),
+        ]);
+        let job = tar_gz(&[
+            ("host-00_27017.tar.gz", &host_00),
+            ("host-01_27017.tar.gz", &host_01),
+        ]);
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path();
+        let archive = directory.join("ftdc_data_my-replica-set_job_new-job-id-73.tar.gz");
+        fs::write(&archive, job).unwrap();
+
+        // When
+        let manifest = extract_archive(&archive, directory.join("out"), None).unwrap();
+
+        // Then
+        let replica_set = directory.join("out/my-replica-set");
+        assert_eq!(manifest.directory, replica_set);
+        assert!(
+            replica_set
+                .join("host-00:27017/diagnostic.data/metrics.interim")
+                .exists()
+        );
+        assert_eq!(
+            fs::read(replica_set.join("host-01:27017/mongodb.log")).unwrap(),
+            b"log line"
+        );
+        assert_eq!(
+            fs::read(replica_set.join("host-01:27017/audit/mongodb.log")).unwrap(),
+            b"audit line"
+        );
+
+        let written: Manifest =
+            serde_json::from_slice(&fs::read(replica_set.join("manifest.json")).unwrap()).unwrap();
+        assert_eq!(written, Manifest { directory: PathBuf::new(), ..manifest });
+        assert_eq!(written.replica_set, "my-replica-set");
+        let [host_00, host_01] = written.hosts.as_slice() else {
+            panic!("expected two hosts, got {:?}", written.hosts);
+        };
+        assert_eq!(host_00.host, "host-00:27017");
+        assert_eq!(host_00.start.as_deref(), Some("2024-01-31T10:45:00Z"));
+        assert_eq!(host_00.end.as_deref(), Some("2024-01-31T10:46:42Z"));
+        assert_eq!(
+            host_00.files[0].path,
+            Path::new("host-00:27017/diagnostic.data/metrics.2024")
+        );
+        assert_eq!(host_01.files.len(), 2);
+        assert_eq!(host_01.files[1].size, 8);
+        assert_eq!(host_01.start, None);
+    }
+
+    #[test]
+    fn given_archive_and_host_names_when_derive_names_then_strip_template_and_port() {
+        // Given
+        let archives = [
+            "out/ftdc_data_my_replica_set_job_new-job-id-73.tar.gz",
+            "unredacted_ftdc_data_my-replica-set_job_new-job-id-73.tgz",
+            "mongodb_data_my-replica-set_job_new-job-id-73.tar.gz",
+            "my_data_set_job_1.tar.gz",
+            "incident.tar.gz",
+        ];
+        let hosts = ["host-00_27017", "my_host", "unknown"];
+
+        // When
+        let replica_sets = archives.map(|archive| replica_set_and_job_of(Path::new(archive)));
+        let hosts = hosts.map(host_and_port);
+
+        // Then
+        let job_id = Some("new-job-id-73".to_string());
+        assert_eq!(
+            replica_sets,
+            [
+                ("my_replica_set".to_string(), job_id.clone()),
+                ("my-replica-set".to_string(), job_id.clone()),
+                ("my-replica-set".to_string(), job_id),
+                ("my_data_set_job_1".to_string(), None),
+                ("incident".to_string(), None)
+            ]
+        );
+        assert_eq!(hosts, ["host-00:27017", "my_host", "unknown"]);
+    }
+}
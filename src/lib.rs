@@ -13,36 +13,56 @@
 //! ## Example
 //!
 //! ```
-//! use error::Error;
-//! use reqwest::Client;
-//! use service::{FtdcDataService, FtdcLoader};
+//! use ftdc::error::Error;
+//! use ftdc::request::{ByteSize, Credentials, FtdcRequest, GroupId, PrivateKey, PublicKey, ReplicaSetName};
+//! use ftdc::service::{FtdcDataService, FtdcLoader};
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Error> {
-//!   let group_key = "...";
-//!   let replica_set_name = "...";
-//!   let size = 10_000_000
-//!   let atlas_public_key = "...";
-//!   let atlas_private_key = "...";
+//!   let request = FtdcRequest::new(GroupId::new("..."), ReplicaSetName::new("..."))
+//!     .with_byte_size(ByteSize::new(10_000_000))
+//!     .with_credentials(Credentials::api_key(PublicKey::new("..."), PrivateKey::new("...")));
 //!
-//!   let service = FtdcDataService::new(Client::new());
+//!   let service = FtdcDataService::builder().build()?;
 //!
 //!   service
-//!     .get_ftdc_data(
-//!       group_key,
-//!       replica_set_name,
-//!       size,
-//!       atlas_public_key,
-//!       atlas_private_key,
-//!     )
+//!     .fetch_ftdc_data(&request)
 //!     .await
-//!     .map(|download_path| println!("Downloaded to: `{download_path}`"))
+//!     .map(|download_path| println!("Downloaded to: `{}`", download_path.display()))
 //! }
 //! ```
 //!
 //! The data is downloaded to the current directory the application was executed in as a `*.tar.gz`.
+//!
+//! ## Decoding FTDC data
+//!
+//! Once extracted, the `metrics.*` files of a `diagnostic.data` directory can be decoded with the
+//! [`decoder`] module. Every metric chunk is inflated into its reference document plus one value
+//! per sample for each metric.
+//!
+//! ```
+//! use ftdc::decoder::{self, FtdcDocument};
+//!
+//! for document in decoder::read_file("diagnostic.data/metrics.interim")? {
+//!   if let FtdcDocument::MetricChunk(chunk) = document {
+//!     let connections = chunk.metric("serverStatus.connections.current");
+//!     println!("{} samples: {connections:?}", chunk.sample_count());
+//!   }
+//! }
+//! ```
 
+mod archive;
+pub mod auth;
+pub mod dataset;
+pub mod decoder;
 pub mod error;
+pub mod export;
+pub mod extract;
 pub mod model;
+pub mod openmetrics;
+pub mod output;
+pub mod poll;
 pub mod progress;
+pub mod request;
+pub mod retry;
 pub mod service;
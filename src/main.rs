@@ -1,32 +1,424 @@
 mod cli;
-mod error;
-mod model;
-mod progress;
-mod service;
+mod picker;
 
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use cli::AtlasArgs;
 use cli::Cli;
-use service::FtdcDataService;
-use service::FtdcLoader;
+use cli::ClustersCommand;
+use cli::Command;
+use cli::ExportArgs;
+use cli::ExtractArgs;
+use cli::FetchArgs;
+use cli::JobsCommand;
+use cli::OpenMetricsArgs;
+use cli::TargetArgs;
+use ftdc::auth::AtlasAuth;
+use ftdc::dataset::FtdcDataset;
+use ftdc::error::Error;
+use ftdc::export;
+use ftdc::export::ExportFormat;
+use ftdc::extract;
+use ftdc::model::ClusterMember;
+use ftdc::model::JobFilter;
+use ftdc::model::JobState;
+use ftdc::model::JobStatus;
+use ftdc::openmetrics;
+use ftdc::output::JobDownload;
+use ftdc::progress::IndicatifReporter;
+use ftdc::service::FtdcDataService;
+use ftdc::service::FtdcLoader;
 
+use bson::DateTime;
 use clap::Parser;
-use error::Error;
 use reqwest::Client;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    let Cli { group_key, replica_set_name, size, atlas_public_key, atlas_private_key } =
-        Cli::parse();
-
-    let service = FtdcDataService::new(Client::new());
+    match Cli::parse().command {
+        Command::Fetch(args) => fetch(args).await,
+        Command::Jobs(command) => jobs(command).await,
+        Command::Clusters(command) => clusters(command).await,
+        Command::Extract(args) => extract(args),
+        Command::Export(args) => export(args),
+        Command::OpenMetrics(args) => export_openmetrics(args),
+    }
+}
 
+fn service(atlas: &AtlasArgs) -> FtdcDataService {
+    let service = FtdcDataService::new(Client::new()).with_flavor(atlas.flavor);
+    let service = match &atlas.base_url {
+        Some(base_url) => service.with_base_url(base_url),
+        None => service,
+    };
     service
-        .get_ftdc_data(
-            &group_key,
-            &replica_set_name,
-            size,
-            &atlas_public_key,
-            &atlas_private_key,
-        )
-        .await
-        .map(|download_path| println!("Downloaded to: `{download_path}`"))
+        .with_api_version(atlas.api_version())
+        .with_retry_config(atlas.retry_config())
+        .with_progress_reporter(IndicatifReporter::new())
+}
+
+async fn fetch(args: FetchArgs) -> Result<(), Error> {
+    let FetchArgs { atlas, target, poll, output, reuse_jobs_within, retry_failed_job } = args;
+    let output_config = output.output_config();
+    output_config.validate()?;
+
+    let auth = atlas.auth();
+    let mut service = service(&atlas)
+        .with_poll_config(poll.poll_config())
+        .with_output_config(output_config)
+        .with_failed_job_retries(u32::from(retry_failed_job))
+        .with_redacted(target.redacted());
+    if let Some(minutes) = reuse_jobs_within {
+        service = service.with_job_reuse(Duration::from_secs(minutes * 60));
+    }
+    confirm_unredacted(&target)?;
+    let log_types = target.log_types()?;
+    let TargetArgs { replica_set_name, cluster_name, include_mongos, shard, size, .. } = target;
+    let group_key = atlas.group_key;
+
+    let (target, downloads) = match (cluster_name, shard, replica_set_name) {
+        (Some(cluster_name), Some(shard), _) => {
+            let downloads = service
+                .get_shard_ftdc_data(&group_key, &cluster_name, shard, size, &log_types, &auth)
+                .await?;
+            (
+                format!("shard {shard} of cluster `{cluster_name}`"),
+                downloads,
+            )
+        }
+        (Some(cluster_name), None, _) => {
+            let downloads = service
+                .get_cluster_ftdc_data(
+                    &group_key,
+                    &cluster_name,
+                    size,
+                    include_mongos,
+                    &log_types,
+                    &auth,
+                )
+                .await?;
+            (format!("cluster `{cluster_name}`"), downloads)
+        }
+        (None, _, None) => {
+            let (cluster_name, members) = pick_target(&service, &group_key, &auth).await?;
+            let target = match &cluster_name {
+                Some(cluster_name) => format!("cluster `{cluster_name}`"),
+                None => format!("replica set `{}`", members[0].name()),
+            };
+            let downloads = service
+                .collect_member_data(
+                    &group_key,
+                    cluster_name.as_deref(),
+                    members,
+                    size,
+                    &log_types,
+                    &auth,
+                )
+                .await?;
+            (target, downloads)
+        }
+        (None, _, Some(replica_set_name)) => {
+            let downloads = service
+                .get_log_data(&group_key, &replica_set_name, size, &log_types, &auth)
+                .await?;
+            (format!("replica set `{replica_set_name}`"), downloads)
+        }
+    };
+
+    println!("Summary for {target}:");
+    for download in &downloads {
+        let name = format!(
+            "{} ({})",
+            download.member.name(),
+            download.log_type.as_str()
+        );
+        match &download.result {
+            Ok(download_path) => {
+                println!("  ✔ {name}: downloaded to `{}`", download_path.display())
+            }
+            Err(e) => println!("  ✘ {name}: {e}"),
+        }
+    }
+
+    let failed = downloads.iter().filter(|d| d.result.is_err()).count();
+    match failed {
+        0 => Ok(()),
+        _ => Err(Error::MongoJob(format!(
+            "{failed} of {total} downloads failed.",
+            total = downloads.len()
+        ))),
+    }
+}
+
+async fn jobs(command: JobsCommand) -> Result<(), Error> {
+    match command {
+        JobsCommand::List { atlas, status, verbose } => {
+            let status = status.as_deref().map(JobState::from_str).transpose()?;
+            let filter = JobFilter { status, verbose };
+            let jobs = service(&atlas)
+                .list_jobs(&atlas.group_key, &filter, &atlas.auth())
+                .await?;
+            jobs.iter().for_each(print_job);
+            Ok(())
+        }
+        JobsCommand::Create { atlas, target } => {
+            let auth = atlas.auth();
+            let service = service(&atlas);
+            let group_key = &atlas.group_key;
+
+            confirm_unredacted(&target)?;
+            let log_types = target.log_types()?;
+            let members = match (&target.cluster_name, target.shard, &target.replica_set_name) {
+                (Some(cluster_name), Some(shard), _) => {
+                    let replica_set = service
+                        .get_shard_replica_set(group_key, cluster_name, shard, &auth)
+                        .await?;
+                    vec![ClusterMember::ReplicaSet(replica_set)]
+                }
+                (Some(cluster_name), None, _) => {
+                    service
+                        .get_cluster_members(group_key, cluster_name, target.include_mongos, &auth)
+                        .await?
+                }
+                (None, _, Some(replica_set_name)) => {
+                    let replica_set = service
+                        .get_replica_set(group_key, replica_set_name, &auth)
+                        .await?;
+                    vec![ClusterMember::ReplicaSet(replica_set)]
+                }
+                (None, _, None) => pick_target(&service, group_key, &auth).await?.1,
+            };
+
+            for member in &members {
+                for log_type in &log_types {
+                    let job = member
+                        .log_collection_job(target.size)
+                        .with_log_types(vec![*log_type])
+                        .with_redacted(target.redacted());
+                    let job_id = service.create_ftdc_job(group_key, &job, &auth).await?;
+                    println!("{}\t{}\t{}", job_id.id, member.name(), log_type.as_str());
+                }
+            }
+            Ok(())
+        }
+        JobsCommand::Status { job_id, atlas } => {
+            let job = service(&atlas)
+                .get_job_status(&atlas.group_key, &job_id, &atlas.auth())
+                .await?;
+            print_job(&job);
+            Ok(())
+        }
+        JobsCommand::Download { job_id, atlas, poll, output } => {
+            let output_config = output.output_config();
+            output_config.validate()?;
+
+            let auth = atlas.auth();
+            let service = service(&atlas)
+                .with_poll_config(poll.poll_config())
+                .with_output_config(output_config);
+            let group_key = &atlas.group_key;
+
+            let job = service.get_job_status(group_key, &job_id, &auth).await?;
+            service.wait_for_job(group_key, &job_id, &auth).await?;
+            let download_path = service
+                .download_ftdc_data(
+                    &JobDownload {
+                        group: group_key,
+                        cluster: None,
+                        replica_set: &job.resource_name,
+                        job_id: &job_id,
+                        log_type: &job.log_types.join("+"),
+                        // Without the field, the data is treated as possibly sensitive.
+                        redacted: job.redacted.unwrap_or(false),
+                    },
+                    &auth,
+                )
+                .await?;
+            println!("Downloaded to: `{}`", download_path.display());
+            Ok(())
+        }
+        JobsCommand::Extend { job_id, days, atlas } => {
+            let expiration_date =
+                DateTime::from_system_time(SystemTime::now() + Duration::from_secs(days * 86_400));
+            service(&atlas)
+                .extend_job_expiration(&atlas.group_key, &job_id, expiration_date, &atlas.auth())
+                .await?;
+            println!("Job {job_id} expires at {expiration_date}.");
+            Ok(())
+        }
+        JobsCommand::Retry { job_id, atlas } => {
+            service(&atlas)
+                .retry_job(&atlas.group_key, &job_id, &atlas.auth())
+                .await?;
+            println!("Retrying job {job_id}.");
+            Ok(())
+        }
+        JobsCommand::Delete { job_id, atlas } => {
+            service(&atlas)
+                .delete_job(&atlas.group_key, &job_id, &atlas.auth())
+                .await?;
+            println!("Deleted job {job_id}.");
+            Ok(())
+        }
+    }
+}
+
+async fn clusters(command: ClustersCommand) -> Result<(), Error> {
+    match command {
+        ClustersCommand::List { atlas } => {
+            let members = service(&atlas)
+                .discover_members(&atlas.group_key, &atlas.auth())
+                .await?;
+            for member in &members {
+                println!(
+                    "{cluster}\t{member_type}\t{name}",
+                    cluster = member.cluster_name.as_deref().unwrap_or("-"),
+                    member_type = member.member_type.as_str(),
+                    name = member.member.name(),
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+fn extract(args: ExtractArgs) -> Result<(), Error> {
+    let ExtractArgs { archive, output_dir, replica_set_name } = args;
+    let output_dir = output_dir.unwrap_or_default();
+    let manifest = extract::extract_archive(&archive, &output_dir, replica_set_name.as_deref())?;
+
+    for host in &manifest.hosts {
+        let time_range = match (&host.start, &host.end) {
+            (Some(start), Some(end)) => format!("{start} - {end}"),
+            _ => "no FTDC samples".to_string(),
+        };
+        println!(
+            "{host}\t{files} files\t{time_range}",
+            host = host.host,
+            files = host.files.len(),
+        );
+    }
+    println!("Extracted to: `{}`", manifest.directory.display());
+    Ok(())
+}
+
+fn export(args: ExportArgs) -> Result<(), Error> {
+    let config = args.export_config()?;
+    let dataset = match args.input.is_dir() {
+        true => FtdcDataset::from_dir(&args.input)?,
+        false => FtdcDataset::from_archive(&args.input)?,
+    };
+
+    match &args.output {
+        Some(output) => {
+            let rows = export::export(&dataset, &config, BufWriter::new(File::create(output)?))?;
+            println!("Exported {rows} rows to: `{}`", output.display());
+        }
+        None if config.format == ExportFormat::Csv => {
+            export::export(&dataset, &config, BufWriter::new(io::stdout()))?;
+        }
+        None => {
+            return Err(Error::InvalidExport(
+                "Pass `--output` to export to Parquet.".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn export_openmetrics(args: OpenMetricsArgs) -> Result<(), Error> {
+    let config = args.openmetrics_config()?;
+    let export = |writer: &mut dyn Write| match args.input.is_dir() {
+        true => openmetrics::export(&FtdcDataset::from_dir(&args.input)?, &config, writer),
+        false => openmetrics::export_archive(&args.input, &config, writer),
+    };
+
+    match &args.output {
+        Some(output) => {
+            let samples = export(&mut BufWriter::new(File::create(output)?))?;
+            println!("Exported {samples} samples to: `{}`", output.display());
+        }
+        None => {
+            export(&mut BufWriter::new(io::stdout()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Without a replica set or cluster name, the target is picked from the members of the group on a
+/// terminal.
+async fn pick_target(
+    service: &FtdcDataService,
+    group_key: &str,
+    auth: &AtlasAuth,
+) -> Result<(Option<String>, Vec<ClusterMember>), Error> {
+    if !io::stdin().is_terminal() {
+        return Err(Error::ReplicaSetNotFound(
+            "Pass `--replica-set-name` or `--cluster-name`. `ftdc clusters list` lists the replica sets of the project."
+                .to_string(),
+        ));
+    }
+    let members = service.discover_members(group_key, auth).await?;
+    picker::pick_members(members)
+}
+
+/// Unredacted logs may contain sensitive information, so collecting them has to be confirmed.
+fn confirm_unredacted(target: &TargetArgs) -> Result<(), Error> {
+    if target.redacted() || target.yes {
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        return Err(Error::Cancelled(
+            "Collecting unredacted logs has to be confirmed. Pass `--yes` to confirm it up front."
+                .to_string(),
+        ));
+    }
+
+    print!(
+        "Unredacted logs may contain sensitive information like query values. Collect them? [y/N] "
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err(Error::Cancelled(
+            "Collecting unredacted logs was cancelled.".to_string(),
+        )),
+    }
+}
+
+fn print_job(job: &JobStatus) {
+    println!(
+        "{id}\t{status}\t{resource}\tcreated: {created}\texpires: {expires}\tsize: {size}",
+        id = job.id,
+        status = job.status,
+        resource = job.resource_name,
+        created = job.creation_date.as_deref().unwrap_or("-"),
+        expires = job.expiration_date.as_deref().unwrap_or("-"),
+        size = job
+            .uncompressed_size_total_bytes
+            .or(job.size_requested_per_file_bytes)
+            .map_or("-".to_string(), |bytes| bytes.to_string()),
+    );
+    for child in &job.child_jobs {
+        println!(
+            "  {host}\t{log_type}\t{status}{error}",
+            host = child.host_name.as_deref().unwrap_or("-"),
+            log_type = child.log_collection_type.as_deref().unwrap_or("-"),
+            status = child.status.as_deref().unwrap_or("-"),
+            error = child
+                .error_message
+                .as_deref()
+                .map_or(String::new(), |error| format!("\t{error}")),
+        );
+    }
 }
@@ -1,10 +1,13 @@
 use std::str::FromStr;
+use std::time::Duration;
 
+use bson::DateTime;
 use serde::Deserialize;
 use serde::Serialize;
 
 use crate::error::Error;
 use crate::error::Error::InvalidJobState;
+use crate::error::Error::InvalidLogType;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -13,7 +16,7 @@ pub struct LogCollectionJob<'a> {
     resource_name: &'a str,
     redacted: bool,
     size_requested_per_file_bytes: u64,
-    log_types: Vec<&'a str>,
+    log_types: Vec<LogType>,
 }
 
 impl LogCollectionJob<'_> {
@@ -23,22 +26,190 @@ impl LogCollectionJob<'_> {
             size_requested_per_file_bytes: bytes,
             resource_type: "REPLICASET",
             redacted: true,
-            log_types: vec!["FTDC"],
+            log_types: vec![LogType::Ftdc],
         }
     }
+
+    /// A job for a single process, e.g. a mongos, addressed as `hostname:port`.
+    pub fn from_process(process: &str, bytes: u64) -> LogCollectionJob<'_> {
+        LogCollectionJob {
+            resource_name: process,
+            size_requested_per_file_bytes: bytes,
+            resource_type: "PROCESS",
+            redacted: true,
+            log_types: vec![LogType::Ftdc],
+        }
+    }
+
+    pub fn resource_name(&self) -> &str {
+        self.resource_name
+    }
+
+    /// The log types to collect, only FTDC data by default.
+    pub fn with_log_types(self, log_types: Vec<LogType>) -> Self {
+        Self { log_types, ..self }
+    }
+
+    pub fn log_types(&self) -> &[LogType] {
+        &self.log_types
+    }
+
+    /// Whether the logs are redacted, `true` by default. Unredacted logs may contain sensitive
+    /// information like query values.
+    pub fn with_redacted(self, redacted: bool) -> Self {
+        Self { redacted, ..self }
+    }
+
+    pub fn redacted(&self) -> bool {
+        self.redacted
+    }
 }
 
+/// The kinds of logs a log collection job can collect.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LogType {
+    /// Full time diagnostic data capture, the `diagnostic.data` directory.
+    #[default]
+    Ftdc,
+    /// The `mongod` and `mongos` logs.
+    Mongodb,
+    AutomationAgent,
+    BackupAgent,
+    MonitoringAgent,
+}
+
+impl LogType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogType::Ftdc => "FTDC",
+            LogType::Mongodb => "MONGODB",
+            LogType::AutomationAgent => "AUTOMATION_AGENT",
+            LogType::BackupAgent => "BACKUP_AGENT",
+            LogType::MonitoringAgent => "MONITORING_AGENT",
+        }
+    }
+}
+
+impl FromStr for LogType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.to_uppercase().as_str() {
+            "FTDC" => Ok(LogType::Ftdc),
+            "MONGODB" => Ok(LogType::Mongodb),
+            "AUTOMATION_AGENT" => Ok(LogType::AutomationAgent),
+            "BACKUP_AGENT" => Ok(LogType::BackupAgent),
+            "MONITORING_AGENT" => Ok(LogType::MonitoringAgent),
+            _ => Err(InvalidLogType(format!(
+                "Unknown log type {s}. Use one of FTDC, MONGODB, AUTOMATION_AGENT, BACKUP_AGENT or MONITORING_AGENT."
+            ))),
+        }
+    }
+}
+
+/// A part of a cluster that FTDC data can be collected for.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ClusterMember {
+    /// A shard, the config server or the replica set of a non sharded cluster.
+    ReplicaSet(String),
+    /// A mongos router, addressed as `hostname:port`.
+    Mongos(String),
+}
+
+impl ClusterMember {
+    pub fn name(&self) -> &str {
+        match self {
+            ClusterMember::ReplicaSet(name) | ClusterMember::Mongos(name) => name,
+        }
+    }
+
+    pub fn log_collection_job(&self, bytes: u64) -> LogCollectionJob<'_> {
+        match self {
+            ClusterMember::ReplicaSet(name) => LogCollectionJob::from(name, bytes),
+            ClusterMember::Mongos(process) => LogCollectionJob::from_process(process, bytes),
+        }
+    }
+}
+
+/// The role of a cluster member, derived from the type name of its processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MemberType {
+    /// The replica set of a non sharded cluster.
+    ReplicaSet,
+    Shard,
+    Config,
+    Mongos,
+}
+
+impl MemberType {
+    pub fn from_type_name(type_name: &str) -> Self {
+        match type_name {
+            name if name.contains("MONGOS") => MemberType::Mongos,
+            name if name.contains("CONFIG") => MemberType::Config,
+            name if name.starts_with("SHARD") => MemberType::Shard,
+            _ => MemberType::ReplicaSet,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemberType::ReplicaSet => "replica set",
+            MemberType::Shard => "shard",
+            MemberType::Config => "config",
+            MemberType::Mongos => "mongos",
+        }
+    }
+}
+
+/// A member of a cluster as found among the processes of a group.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DiscoveredMember {
+    /// The cluster name, `None` if the host names do not follow the Atlas naming scheme.
+    pub cluster_name: Option<String>,
+    pub member_type: MemberType,
+    pub member: ClusterMember,
+}
+
+/// One page of the results of a list endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub results: Vec<T>,
+    #[serde(default)]
+    pub total_count: Option<u64>,
+}
+
+/// A page of the processes of a group.
+pub type Clusters = Page<Shard>;
+
+/// A page of the clusters of a group.
+pub type ClusterDescriptions = Page<ClusterDescription>;
+
+/// An Atlas cluster as described by the clusters endpoint.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct Clusters {
-    pub results: Vec<Shard>,
+#[serde(rename_all = "camelCase")]
+pub struct ClusterDescription {
+    pub name: String,
+    /// `REPLICASET`, `SHARDED` or `GEOSHARDED`.
+    #[serde(default)]
+    pub cluster_type: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Shard {
+    /// The name of the process in the Atlas UI. Cloud and Ops Manager hosts do not have one.
+    #[serde(default)]
     pub user_alias: String,
     pub type_name: String,
     pub replica_set_name: Option<String>,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub hostname: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -46,15 +217,118 @@ pub struct JobId {
     pub id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    pub id: String,
+    #[serde(default)]
+    pub download_url: String,
+    pub status: String,
+    /// The replica set or process (`hostname:port`) the job collects data of.
+    #[serde(default)]
+    pub resource_name: String,
+    #[serde(default)]
+    pub resource_type: String,
+    #[serde(default)]
+    pub log_types: Vec<String>,
+    #[serde(default)]
+    pub redacted: Option<bool>,
+    #[serde(default)]
+    pub size_requested_per_file_bytes: Option<u64>,
+    #[serde(default)]
+    pub uncompressed_size_total_bytes: Option<u64>,
+    /// ISO 8601 timestamp, e.g. `2024-01-31T10:45:00Z`.
+    #[serde(default)]
+    pub creation_date: Option<String>,
+    /// ISO 8601 timestamp after which the data of the job cannot be downloaded anymore.
+    #[serde(default)]
+    pub expiration_date: Option<String>,
+    /// The collection per host, only returned when listing jobs verbosely.
+    #[serde(default)]
+    pub child_jobs: Vec<ChildJob>,
+}
+
+impl JobStatus {
+    pub fn created_at(&self) -> Option<DateTime> {
+        parse_date(self.creation_date.as_deref())
+    }
+
+    pub fn expires_at(&self) -> Option<DateTime> {
+        parse_date(self.expiration_date.as_deref())
+    }
+
+    /// Whether the data of this job can be downloaded instead of collecting it again for `job`:
+    /// the job succeeded for the same resource, log types and redaction, was created less than
+    /// `max_age` before `now` and has not expired yet. A job of unknown redaction is not reused.
+    pub fn can_be_reused_for(
+        &self,
+        job: &LogCollectionJob,
+        max_age: Duration,
+        now: DateTime,
+    ) -> bool {
+        let same_log_types = self.log_types.len() == job.log_types.len()
+            && job
+                .log_types
+                .iter()
+                .all(|log_type| self.log_types.iter().any(|t| t == log_type.as_str()));
+        let recent = self.created_at().is_some_and(|created_at| {
+            now.checked_duration_since(created_at)
+                .is_some_and(|age| age <= max_age)
+        });
+        let unexpired = self.expires_at().is_some_and(|expires_at| expires_at > now);
+
+        matches!(JobState::from_str(&self.status), Ok(JobState::Succcess))
+            && self.resource_name == job.resource_name
+            && same_log_types
+            && self.redacted == Some(job.redacted)
+            && recent
+            && unexpired
+    }
+}
+
+fn parse_date(date: Option<&str>) -> Option<DateTime> {
+    date.and_then(|date| DateTime::parse_rfc3339_str(date).ok())
+}
+
+/// The log collection of a single host within a job.
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct JobStatus<'a> {
-    pub id: &'a str,
-    pub download_url: &'a str,
-    pub status: &'a str,
+pub struct ChildJob {
+    #[serde(default)]
+    pub host_name: Option<String>,
+    #[serde(default)]
+    pub log_collection_type: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub error_message: Option<String>,
+    #[serde(default)]
+    pub start_date: Option<String>,
+    #[serde(default)]
+    pub finish_date: Option<String>,
 }
 
+/// The body to extend the expiration of a job with.
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobExpiration {
+    /// ISO 8601 timestamp, e.g. `2024-01-31T10:45:00Z`.
+    pub expiration_date: String,
+}
+
+/// Narrows down the jobs returned when listing the log collection jobs of a project.
+#[derive(Debug, Clone, Default)]
+pub struct JobFilter {
+    /// Only return jobs in this state.
+    pub status: Option<JobState>,
+    /// Also return the child jobs of every job.
+    pub verbose: bool,
+}
+
+/// A page of the log collection jobs of a group.
+pub type LogCollectionJobs = Page<JobStatus>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum JobState {
     Succcess,
     Failure,
@@ -63,6 +337,18 @@ pub enum JobState {
     Expired,
 }
 
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Succcess => "SUCCESS",
+            JobState::Failure => "FAILURE",
+            JobState::InProgress => "IN_PROGRESS",
+            JobState::MarkedForExpiry => "MARKED_FOR_EXPIRY",
+            JobState::Expired => "EXPIRED",
+        }
+    }
+}
+
 impl FromStr for JobState {
     type Err = Error;
 
@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::Path;
+
+use crate::dataset::FtdcDataset;
+use crate::dataset::MetricSeries;
+use crate::error::Error;
+use crate::export::MetricFilter;
+use crate::extract;
+
+/// The prefix of all metric names if none is given.
+pub const DEFAULT_PREFIX: &str = "mongodb_ftdc";
+
+/// What to expose and how to label it.
+#[derive(Debug, Clone)]
+pub struct OpenMetricsConfig {
+    pub filter: MetricFilter,
+    /// Prepended to every metric name, e.g. `mongodb_ftdc_serverStatus_connections_current`.
+    pub prefix: String,
+    /// The value of the `replica_set` label, left out if `None`.
+    pub replica_set: Option<String>,
+    /// The value of the `job_id` label, left out if `None`.
+    pub job_id: Option<String>,
+}
+
+impl Default for OpenMetricsConfig {
+    fn default() -> Self {
+        Self {
+            filter: MetricFilter::default(),
+            prefix: DEFAULT_PREFIX.to_string(),
+            replica_set: None,
+            job_id: None,
+        }
+    }
+}
+
+impl OpenMetricsConfig {
+    pub fn with_filter(self, filter: MetricFilter) -> Self {
+        Self { filter, ..self }
+    }
+
+    pub fn with_prefix(self, prefix: impl Into<String>) -> Self {
+        Self { prefix: prefix.into(), ..self }
+    }
+
+    pub fn with_replica_set(self, replica_set: impl Into<String>) -> Self {
+        Self { replica_set: Some(replica_set.into()), ..self }
+    }
+
+    pub fn with_job_id(self, job_id: impl Into<String>) -> Self {
+        Self { job_id: Some(job_id.into()), ..self }
+    }
+}
+
+/// Maps a dotted FTDC path to a valid metric name, e.g. `serverStatus.wiredTiger.cache.bytes read
+/// into cache` to `mongodb_ftdc_serverStatus_wiredTiger_cache_bytes_read_into_cache`. Every
+/// character other than an ASCII letter or digit becomes `_`, runs of `_` are collapsed.
+pub fn metric_name(prefix: &str, path: &str) -> String {
+    let mut name = String::with_capacity(prefix.len() + path.len() + 1);
+    for char in prefix.chars().chain(['_']).chain(path.chars()) {
+        let char = match char.is_ascii_alphanumeric() {
+            true => char,
+            false => '_',
+        };
+        if char != '_' || !name.ends_with('_') {
+            name.push(char);
+        }
+    }
+
+    let name = name.trim_matches('_');
+    match name.starts_with(|char: char| char.is_ascii_digit()) || name.is_empty() {
+        true => format!("_{name}"),
+        false => name.to_string(),
+    }
+}
+
+/// Writes the metrics of `dataset` selected by the filter of `config` as OpenMetrics text with a
+/// timestamp per sample and returns the number of samples written. Every metric becomes a gauge
+/// labeled with `host` and, if given, `replica_set` and `job_id`. Paths that map to the same
+/// metric name, e.g. `a.b c` and `a.b_c`, are told apart by a `path` label with the dotted path.
+///
+/// The output ends with `# EOF` and can be turned into Prometheus blocks with
+/// `promtool tsdb create-blocks-from openmetrics`.
+pub fn export(
+    dataset: &FtdcDataset,
+    config: &OpenMetricsConfig,
+    mut writer: impl Write,
+) -> Result<usize, Error> {
+    let mut families: BTreeMap<String, Vec<&MetricSeries>> = BTreeMap::new();
+    for series in dataset
+        .iter()
+        .filter(|series| config.filter.matches(&series.path))
+    {
+        families
+            .entry(metric_name(&config.prefix, &series.path))
+            .or_default()
+            .push(series);
+    }
+
+    let mut samples = 0;
+    for (name, series) in &families {
+        let paths: BTreeSet<&str> = series.iter().map(|series| series.path.as_str()).collect();
+        let collides = paths.len() > 1;
+
+        writeln!(writer, "# TYPE {name} gauge")?;
+        for series in series {
+            let path = collides.then_some(series.path.as_str());
+            let labels = labels(&series.host, path, config);
+            for (timestamp, value) in series.samples() {
+                let millis = timestamp.timestamp_millis();
+                writeln!(
+                    writer,
+                    "{name}{{{labels}}} {value} {}.{:03}",
+                    millis.div_euclid(1_000),
+                    millis.rem_euclid(1_000)
+                )?;
+                samples += 1;
+            }
+        }
+    }
+    writeln!(writer, "# EOF")?;
+    writer.flush()?;
+    Ok(samples)
+}
+
+/// Decodes the `*.tar.gz` written by `download_ftdc_data` and writes its metrics like [`export`].
+/// A replica set or job id not set in `config` is taken from the default archive name
+/// `{log_type}_data_{replica_set}_job_{job_id}.tar.gz`.
+pub fn export_archive(
+    archive: impl AsRef<Path>,
+    config: &OpenMetricsConfig,
+    writer: impl Write,
+) -> Result<usize, Error> {
+    let archive = archive.as_ref();
+    let (replica_set, job_id) = extract::replica_set_and_job_of(archive);
+    let config = OpenMetricsConfig {
+        replica_set: config.replica_set.clone().or(Some(replica_set)),
+        job_id: config.job_id.clone().or(job_id),
+        ..config.clone()
+    };
+
+    let dataset = FtdcDataset::from_archive(archive)?;
+    export(&dataset, &config, writer)
+}
+
+fn labels(host: &str, path: Option<&str>, config: &OpenMetricsConfig) -> String {
+    let host = extract::host_and_port(host);
+    [
+        ("host", Some(host.as_str())),
+        ("replica_set", config.replica_set.as_deref()),
+        ("job_id", config.job_id.as_deref()),
+        ("path", path),
+    ]
+    .into_iter()
+    .filter_map(|(label, value)| value.map(|value| format!("{label}=\"{}\"", escape(value))))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use bson::DateTime;
+    use bson::doc;
+    use tempfile::tempdir;
+
+    use crate::archive::tests::tar_gz;
+    use crate::dataset::tests::metrics_file;
+    use crate::export::MetricFilter;
+    use crate::export::tests::dataset;
+    use crate::export::tests::dataset_of;
+    use crate::openmetrics::OpenMetricsConfig;
+    use crate::openmetrics::export;
+    use crate::openmetrics::export_archive;
+    use crate::openmetrics::metric_name;
+
+    #[test]
+    fn given_dotted_paths_when_metric_name_then_get_valid_metric_names() {
+        // Given
+        let paths = [
+            "serverStatus.connections.current",
+            "serverStatus.wiredTiger.cache.bytes read into cache",
+            "replSetGetStatus.members.0.health",
+            "systemMetrics.disks.nvme0n1.io_time_ms",
+        ];
+
+        // When
+        let names = paths.map(|path| metric_name("mongodb_ftdc", path));
+        let unprefixed = metric_name("", "0.optime");
+
+        // Then
+        assert_eq!(
+            names,
+            [
+                "mongodb_ftdc_serverStatus_connections_current",
+                "mongodb_ftdc_serverStatus_wiredTiger_cache_bytes_read_into_cache",
+                "mongodb_ftdc_replSetGetStatus_members_0_health",
+                "mongodb_ftdc_systemMetrics_disks_nvme0n1_io_time_ms",
+            ]
+        );
+        assert_eq!(unprefixed, "_0_optime");
+    }
+
+    #[test]
+    fn given_dataset_when_export_then_write_timestamped_gauges_with_labels() {
+        // Given
+        let dataset = dataset(
+            "host-00_27017",
+            1_706_697_900_000,
+            &[vec![1_500], vec![2], vec![0]],
+        );
+        let config = OpenMetricsConfig::default()
+            .with_filter(MetricFilter::default().with_glob("**.current").unwrap())
+            .with_replica_set("my \"replica\" set")
+            .with_job_id("my-job-id");
+        let mut text = Vec::new();
+
+        // When
+        let samples = export(&dataset, &config, &mut text).unwrap();
+
+        // Then
+        assert_eq!(samples, 2);
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "# TYPE mongodb_ftdc_serverStatus_connections_current gauge\n\
+             mongodb_ftdc_serverStatus_connections_current{host=\"host-00:27017\",replica_set=\"my \\\"replica\\\" set\",job_id=\"my-job-id\"} 5 1706697900.000\n\
+             mongodb_ftdc_serverStatus_connections_current{host=\"host-00:27017\",replica_set=\"my \\\"replica\\\" set\",job_id=\"my-job-id\"} 7 1706697901.500\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn given_paths_mapping_to_the_same_name_when_export_then_label_them_with_their_path() {
+        // Given
+        let reference = doc! {
+            "start": DateTime::from_millis(10_000),
+            "a": { "b c": 1, "b_c": 2 },
+        };
+        let dataset = dataset_of(
+            "host-00_27017",
+            10_000,
+            &reference,
+            &[vec![], vec![], vec![]],
+        );
+        let config = OpenMetricsConfig::default()
+            .with_prefix("ftdc")
+            .with_filter(MetricFilter::default().with_glob("a.*").unwrap());
+        let mut text = Vec::new();
+
+        // When
+        let samples = export(&dataset, &config, &mut text).unwrap();
+
+        // Then
+        assert_eq!(samples, 2);
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "# TYPE ftdc_a_b_c gauge\n\
+             ftdc_a_b_c{host=\"host-00:27017\",path=\"a.b c\"} 1 10.000\n\
+             ftdc_a_b_c{host=\"host-00:27017\",path=\"a.b_c\"} 2 10.000\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn given_job_archive_when_export_archive_then_label_with_replica_set_and_job_id() {
+        // Given
+        let host_00 = tar_gz(&[("diagnostic.data/metrics.interim", &metrics_file(10_000, 5))]);
+        let job = tar_gz(&[("host-00_27017.tar.gz", &host_00)]);
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path();
+        let archive = directory.join("ftdc_data_my-replica-set_job_new-job-id-73.tar.gz");
+        fs::write(&archive, job).unwrap();
+        let mut text = Vec::new();
+
+        // When
+        let samples = export_archive(&archive, &OpenMetricsConfig::default(), &mut text).unwrap();
+
+        // Then
+        let text = String::from_utf8(text).unwrap();
+        assert_eq!(samples, 6);
+        assert!(text.contains(
+            "{host=\"host-00:27017\",replica_set=\"my-replica-set\",job_id=\"new-job-id-73\"} 6 11.000\n"
+        ));
+        assert!(text.ends_with("# EOF\n"));
+    }
+}
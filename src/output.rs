@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::error::Error;
+
+/// The file name data is downloaded to unless configured otherwise, e.g.
+/// `ftdc_data_{replica_set}_job_{job_id}.tar.gz` for FTDC data and `mongodb_data_...` for the
+/// `MONGODB` logs, so archives of several log types tell themselves apart.
+pub const DEFAULT_FILE_NAME_TEMPLATE: &str = "{log_type}_data_{replica_set}_job_{job_id}.tar.gz";
+
+/// Prepended to the file name of unredacted data unless the template uses `{redaction}`.
+const UNREDACTED_PREFIX: &str = "unredacted_";
+
+const PLACEHOLDERS: [&str; 7] = [
+    "group",
+    "cluster",
+    "replica_set",
+    "job_id",
+    "log_type",
+    "redaction",
+    "timestamp",
+];
+
+/// Where downloaded FTDC data is written to.
+///
+/// The file name is rendered from a template with the placeholders `{group}`, `{cluster}`,
+/// `{replica_set}`, `{job_id}`, `{log_type}` (e.g. `ftdc` or `mongodb`), `{redaction}`
+/// (`redacted` or `unredacted`) and `{timestamp}` (UTC, e.g. `20240131T104500Z`). The file name
+/// of unredacted data is prefixed with `unredacted_` if the template does not use `{redaction}`.
+/// Existing files are only overwritten if `overwrite` is set.
+#[derive(Debug, Clone)]
+pub struct OutputConfig {
+    /// The directory to write to, the current directory if `None`.
+    pub directory: Option<PathBuf>,
+    pub file_name_template: String,
+    pub overwrite: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            file_name_template: DEFAULT_FILE_NAME_TEMPLATE.to_string(),
+            overwrite: false,
+        }
+    }
+}
+
+/// A finished job to download, with the values the placeholders of a file name template are
+/// replaced with.
+#[derive(Debug, Clone)]
+pub struct JobDownload<'a> {
+    pub group: &'a str,
+    /// The cluster name, `{cluster}` falls back to the replica set name if `None`.
+    pub cluster: Option<&'a str>,
+    /// The replica set or process (`hostname:port`) the job collected data of.
+    pub replica_set: &'a str,
+    pub job_id: &'a str,
+    /// The log types of the job, e.g. `FTDC`.
+    pub log_type: &'a str,
+    pub redacted: bool,
+}
+
+impl OutputConfig {
+    pub fn with_directory(self, directory: impl Into<PathBuf>) -> Self {
+        Self { directory: Some(directory.into()), ..self }
+    }
+
+    pub fn with_file_name_template(self, file_name_template: &str) -> Self {
+        Self { file_name_template: file_name_template.to_string(), ..self }
+    }
+
+    pub fn with_overwrite(self, overwrite: bool) -> Self {
+        Self { overwrite, ..self }
+    }
+
+    /// Checks that the template only uses known placeholders.
+    pub fn validate(&self) -> Result<(), Error> {
+        let values = JobDownload {
+            group: "",
+            cluster: None,
+            replica_set: "",
+            job_id: "",
+            log_type: "",
+            redacted: true,
+        };
+        self.render(&values, SystemTime::now()).map(|_| ())
+    }
+
+    /// The absolute path to download to. Fails if the file exists and may not be overwritten.
+    pub(crate) fn file_path(&self, values: &JobDownload) -> Result<PathBuf, Error> {
+        let file_path = self.target_path(values, SystemTime::now())?;
+        if file_path.exists() && !self.overwrite {
+            return Err(Error::OutputExists(format!(
+                "`{file}` already exists. Use another output directory or file name, or allow to overwrite it.",
+                file = file_path.display()
+            )));
+        }
+        Ok(file_path)
+    }
+
+    /// Fails if two of `downloads` would be written to the same path, e.g. every member of a
+    /// cluster with the template `{cluster}.tar.gz`. The jobs are not created yet, so each
+    /// download is rendered with a job id of its own.
+    pub(crate) fn check_distinct_paths(&self, downloads: &[JobDownload]) -> Result<(), Error> {
+        let now = SystemTime::now();
+        let mut targets: BTreeMap<PathBuf, &JobDownload> = BTreeMap::new();
+        for (index, download) in downloads.iter().enumerate() {
+            let job_id = format!("job-{index}");
+            let file_path =
+                self.target_path(&JobDownload { job_id: &job_id, ..download.clone() }, now)?;
+            if let Some(other) = targets.insert(file_path.clone(), download) {
+                return Err(Error::OutputExists(format!(
+                    "The {} data of `{}` and the {} data of `{}` would both be written to `{file}`. Use `{{replica_set}}` and `{{log_type}}` in the file name template.",
+                    other.log_type,
+                    other.replica_set,
+                    download.log_type,
+                    download.replica_set,
+                    file = file_path.display()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn target_path(&self, values: &JobDownload, now: SystemTime) -> Result<PathBuf, Error> {
+        let directory = match &self.directory {
+            Some(directory) if directory.is_absolute() => directory.clone(),
+            Some(directory) => env::current_dir()?.join(directory),
+            None => env::current_dir()?,
+        };
+        let mut file_name = self.render(values, now)?;
+
+        // Unredacted data must never be mistaken for redacted data, whatever the template says.
+        if !values.redacted && !self.file_name_template.contains("{redaction}") {
+            file_name = match file_name.rsplit_once('/') {
+                Some((directories, name)) => format!("{directories}/{UNREDACTED_PREFIX}{name}"),
+                None => format!("{UNREDACTED_PREFIX}{file_name}"),
+            };
+        }
+        Ok(directory.join(file_name))
+    }
+
+    fn render(&self, values: &JobDownload, now: SystemTime) -> Result<String, Error> {
+        let mut rendered = String::with_capacity(self.file_name_template.len());
+        let mut rest = self.file_name_template.as_str();
+
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| {
+                    Error::InvalidTemplate(format!(
+                        "Unclosed placeholder in file name template `{template}`.",
+                        template = self.file_name_template
+                    ))
+                })?;
+
+            let value = match &rest[start + 1..end] {
+                "group" => values.group.to_string(),
+                "cluster" => values.cluster.unwrap_or(values.replica_set).to_string(),
+                "replica_set" => values.replica_set.to_string(),
+                "job_id" => values.job_id.to_string(),
+                "log_type" => values.log_type.to_lowercase(),
+                "redaction" => match values.redacted {
+                    true => "redacted".to_string(),
+                    false => "unredacted".to_string(),
+                },
+                "timestamp" => format_timestamp(now),
+                unknown => {
+                    return Err(Error::InvalidTemplate(format!(
+                        "Unknown placeholder `{{{unknown}}}` in file name template `{template}`. Known placeholders: {known}.",
+                        template = self.file_name_template,
+                        known = PLACEHOLDERS.map(|p| format!("{{{p}}}")).join(", ")
+                    )));
+                }
+            };
+            // Values end up in a file name: mongos processes are addressed as `host:port`.
+            rendered.push_str(&value.replace([':', '/'], "_"));
+            rest = &rest[end + 1..];
+        }
+        rendered.push_str(rest);
+
+        Ok(rendered)
+    }
+}
+
+/// Formats a point in time as basic ISO 8601 in UTC, e.g. `20240131T104500Z`.
+fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, seconds_of_day) = (seconds / 86_400, seconds % 86_400);
+
+    // Converts days since the epoch into a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}{month:02}{day:02}T{hours:02}{minutes:02}{seconds:02}Z",
+        hours = seconds_of_day / 3_600,
+        minutes = seconds_of_day % 3_600 / 60,
+        seconds = seconds_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+    use std::time::UNIX_EPOCH;
+
+    use tempfile::tempdir;
+
+    use crate::error::Error;
+    use crate::output::JobDownload;
+    use crate::output::OutputConfig;
+
+    fn values() -> JobDownload<'static> {
+        JobDownload {
+            group: "my-group-key",
+            cluster: Some("prod"),
+            replica_set: "atlas-x-shard-0",
+            job_id: "new-job-id-73",
+            log_type: "MONGODB",
+            redacted: true,
+        }
+    }
+
+    #[test]
+    fn given_template_with_all_placeholders_when_render_then_replace_them() {
+        // Given
+        let output = OutputConfig::default().with_file_name_template(
+            "{group}/{cluster}-{replica_set}-{log_type}-{job_id}-{timestamp}.tgz",
+        );
+        let now = UNIX_EPOCH + Duration::from_secs(1_706_697_900);
+
+        // When
+        let file_name = output.render(&values(), now).unwrap();
+
+        // Then
+        assert_eq!(
+            file_name,
+            "my-group-key/prod-atlas-x-shard-0-mongodb-new-job-id-73-20240131T104500Z.tgz"
+        );
+    }
+
+    #[test]
+    fn given_default_template_when_render_then_name_archive_after_its_log_type() {
+        // Given
+        let output = OutputConfig::default();
+        let now = UNIX_EPOCH;
+
+        // When
+        let ftdc = output
+            .render(&JobDownload { log_type: "FTDC", ..values() }, now)
+            .unwrap();
+        let mongodb = output.render(&values(), now).unwrap();
+
+        // Then
+        assert_eq!(ftdc, "ftdc_data_atlas-x-shard-0_job_new-job-id-73.tar.gz");
+        assert_eq!(
+            mongodb,
+            "mongodb_data_atlas-x-shard-0_job_new-job-id-73.tar.gz"
+        );
+    }
+
+    #[test]
+    fn given_unknown_placeholder_when_validate_then_invalid_template_error() {
+        // Given
+        let output = OutputConfig::default().with_file_name_template("ftdc_{host}.tar.gz");
+
+        // When
+        let error = output.validate().unwrap_err().to_string();
+
+        // Then
+        assert_eq!(
+            error,
+            "Unknown placeholder `{host}` in file name template `ftdc_{host}.tar.gz`. Known placeholders: {group}, {cluster}, {replica_set}, {job_id}, {log_type}, {redaction}, {timestamp}."
+        );
+    }
+
+    #[test]
+    fn given_existing_file_when_file_path_then_refuse_unless_overwrite() {
+        // Given
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path();
+        fs::write(directory.join("existing.tar.gz"), b"").unwrap();
+        let output = OutputConfig::default()
+            .with_directory(directory)
+            .with_file_name_template("existing.tar.gz");
+
+        // When
+        let refused = output.file_path(&values());
+        let overwritten = output.clone().with_overwrite(true).file_path(&values());
+
+        // Then
+        assert!(refused.unwrap_err().to_string().ends_with(
+            "already exists. Use another output directory or file name, or allow to overwrite it."
+        ));
+        assert_eq!(overwritten.unwrap(), directory.join("existing.tar.gz"));
+    }
+
+    #[test]
+    fn given_downloads_sharing_a_file_name_when_check_distinct_paths_then_output_exists_error() {
+        // Given
+        let output = OutputConfig::default().with_file_name_template("{cluster}-{job_id}.tar.gz");
+        let by_cluster = output.clone().with_file_name_template("{cluster}.tar.gz");
+        let downloads = [
+            JobDownload { replica_set: "atlas-x-shard-0", ..values() },
+            JobDownload { replica_set: "atlas-x-shard-1", ..values() },
+        ];
+
+        // When
+        let distinct = output.check_distinct_paths(&downloads);
+        let shared = by_cluster.check_distinct_paths(&downloads);
+
+        // Then
+        assert!(distinct.is_ok());
+        assert!(matches!(shared, Err(Error::OutputExists(_))));
+    }
+
+    #[test]
+    fn given_unredacted_job_when_file_path_then_mark_file_name_as_unredacted() {
+        // Given
+        let temp_dir = tempdir().unwrap();
+        let directory = temp_dir.path();
+        let output = OutputConfig::default().with_directory(directory);
+        let with_redaction = output
+            .clone()
+            .with_file_name_template("{replica_set}/{redaction}.tar.gz");
+        let unredacted = JobDownload { redacted: false, log_type: "FTDC", ..values() };
+
+        // When
+        let file_path = output.file_path(&unredacted).unwrap();
+        let with_redaction = with_redaction.file_path(&unredacted).unwrap();
+
+        // Then
+        assert_eq!(
+            file_path,
+            directory.join("unredacted_ftdc_data_atlas-x-shard-0_job_new-job-id-73.tar.gz")
+        );
+        assert_eq!(
+            with_redaction,
+            directory.join("atlas-x-shard-0/unredacted.tar.gz")
+        );
+    }
+}
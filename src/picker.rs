@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use dialoguer::FuzzySelect;
+use dialoguer::MultiSelect;
+use dialoguer::theme::ColorfulTheme;
+use ftdc::error::Error;
+use ftdc::model::ClusterMember;
+use ftdc::model::DiscoveredMember;
+use ftdc::model::MemberType;
+
+/// Lets the user search a cluster and pick one or several of its members. Shards and config
+/// servers are preselected, mongos processes are not. Returns the cluster name, if any, along
+/// with the picked members.
+pub(crate) fn pick_members(
+    members: Vec<DiscoveredMember>,
+) -> Result<(Option<String>, Vec<ClusterMember>), Error> {
+    let mut clusters: BTreeMap<String, Vec<DiscoveredMember>> = BTreeMap::new();
+    for member in members {
+        let cluster = match &member.cluster_name {
+            Some(cluster_name) => cluster_name.clone(),
+            None => member.member.name().to_string(),
+        };
+        clusters.entry(cluster).or_default().push(member);
+    }
+    if clusters.is_empty() {
+        return Err(Error::ReplicaSetNotFound(
+            "No replica set found in the group".to_string(),
+        ));
+    }
+
+    let theme = ColorfulTheme::default();
+    let labels: Vec<String> = clusters
+        .iter()
+        .map(|(cluster, members)| format!("{cluster} ({} members)", members.len()))
+        .collect();
+    let cluster = FuzzySelect::with_theme(&theme)
+        .with_prompt("Cluster (type to search)")
+        .items(&labels)
+        .default(0)
+        .interact_opt()
+        .map_err(io::Error::from)?
+        .ok_or_else(cancelled)?;
+    let members = clusters
+        .into_values()
+        .nth(cluster)
+        .expect("the picked cluster is one of the listed clusters");
+
+    let labels: Vec<String> = members
+        .iter()
+        .map(|m| format!("{:<11} {}", m.member_type.as_str(), m.member.name()))
+        .collect();
+    let defaults: Vec<bool> = members
+        .iter()
+        .map(|m| m.member_type != MemberType::Mongos)
+        .collect();
+    let picked = MultiSelect::with_theme(&theme)
+        .with_prompt("Members (space to toggle, enter to confirm)")
+        .items(&labels)
+        .defaults(&defaults)
+        .interact_opt()
+        .map_err(io::Error::from)?
+        .filter(|picked| !picked.is_empty())
+        .ok_or_else(cancelled)?;
+
+    let cluster_name = members[0].cluster_name.clone();
+    let members = picked
+        .into_iter()
+        .map(|index| members[index].member.clone())
+        .collect();
+    Ok((cluster_name, members))
+}
+
+fn cancelled() -> Error {
+    Error::Cancelled("No replica set was picked.".to_string())
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+
+use crate::error::Error;
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(3);
+const DEFAULT_MAX_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_BACKOFF_FACTOR: f64 = 1.5;
+
+/// How the state of a log collection job is polled until the job is done. The interval starts at
+/// `interval` and grows by `backoff_factor` with every poll up to `max_interval`.
+///
+/// Polling stops with [`Error::JobTimeout`] once `timeout` has passed and with
+/// [`Error::Cancelled`] once `cancellation` is cancelled. Dropping the future also stops polling.
+#[derive(Debug, Clone)]
+pub struct PollConfig {
+    pub interval: Duration,
+    pub max_interval: Duration,
+    pub backoff_factor: f64,
+    pub timeout: Option<Duration>,
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_INTERVAL,
+            max_interval: DEFAULT_MAX_INTERVAL,
+            backoff_factor: DEFAULT_BACKOFF_FACTOR,
+            timeout: None,
+            cancellation: None,
+        }
+    }
+}
+
+impl PollConfig {
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self { interval, ..self }
+    }
+
+    pub fn with_max_interval(self, max_interval: Duration) -> Self {
+        Self { max_interval, ..self }
+    }
+
+    pub fn with_backoff_factor(self, backoff_factor: f64) -> Self {
+        Self { backoff_factor, ..self }
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout: Some(timeout), ..self }
+    }
+
+    pub fn with_cancellation(self, cancellation: CancellationToken) -> Self {
+        Self { cancellation: Some(cancellation), ..self }
+    }
+
+    /// Factors below `1.0` keep the interval, an interval too large for a `Duration` (e.g. with
+    /// an infinite factor) becomes `max_interval`.
+    pub(crate) fn next_interval(&self, interval: Duration) -> Duration {
+        let max_interval = self.max_interval.max(self.interval);
+        Duration::try_from_secs_f64(interval.as_secs_f64() * self.backoff_factor.max(1.0))
+            .map_or(max_interval, |next| next.min(max_interval))
+    }
+
+    pub(crate) fn check_cancelled(&self, job_id: &str) -> Result<(), Error> {
+        match &self.cancellation {
+            Some(cancellation) if cancellation.is_cancelled() => Err(cancelled(job_id)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sleeps for `interval` unless the cancellation token fires first.
+    pub(crate) async fn wait(&self, interval: Duration, job_id: &str) -> Result<(), Error> {
+        match &self.cancellation {
+            Some(cancellation) => tokio::select! {
+                _ = time::sleep(interval) => Ok(()),
+                _ = cancellation.cancelled() => Err(cancelled(job_id)),
+            },
+            None => {
+                time::sleep(interval).await;
+                Ok(())
+            }
+        }
+    }
+}
+
+fn cancelled(job_id: &str) -> Error {
+    Error::Cancelled(format!("Polling the state of job {job_id} was cancelled."))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::poll::PollConfig;
+
+    #[test]
+    fn given_backoff_factor_when_next_interval_then_grow_up_to_max_interval() {
+        // Given
+        let poll_config = PollConfig::default()
+            .with_interval(Duration::from_secs(2))
+            .with_max_interval(Duration::from_secs(5))
+            .with_backoff_factor(2.0);
+
+        // When
+        let first = poll_config.next_interval(poll_config.interval);
+        let second = poll_config.next_interval(first);
+
+        // Then
+        assert_eq!(first, Duration::from_secs(4));
+        assert_eq!(second, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn given_huge_backoff_factor_when_next_interval_then_max_interval() {
+        // Given
+        let infinite = PollConfig::default().with_backoff_factor(f64::INFINITY);
+        let huge = PollConfig::default().with_backoff_factor(f64::MAX);
+        let nan = PollConfig::default().with_backoff_factor(f64::NAN);
+
+        // When
+        let intervals =
+            [&infinite, &huge, &nan].map(|config| config.next_interval(config.interval));
+
+        // Then
+        assert_eq!(
+            intervals,
+            [
+                Duration::from_secs(30),
+                Duration::from_secs(30),
+                Duration::from_secs(3)
+            ]
+        );
+    }
+}
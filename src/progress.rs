@@ -1,35 +1,53 @@
-use std::time::Duration;
-
-use indicatif::ProgressBar;
-use indicatif::ProgressStyle;
+use std::path::Path;
 
 use crate::error::Error;
+use crate::model::JobState;
+
+#[cfg(feature = "indicatif")]
+mod indicatif_reporter;
+#[cfg(feature = "tracing")]
+mod tracing_reporter;
+
+#[cfg(feature = "indicatif")]
+pub use indicatif_reporter::DownloadBarHelper;
+#[cfg(feature = "indicatif")]
+pub use indicatif_reporter::IndicatifReporter;
+#[cfg(feature = "indicatif")]
+pub use indicatif_reporter::SpinnerHelper;
+#[cfg(feature = "tracing")]
+pub use tracing_reporter::TracingReporter;
+
+/// Receives the progress of the jobs `FtdcDataService` creates, polls and downloads. Every method
+/// does nothing by default, so an implementation only overrides what it is interested in.
+///
+/// The service reports to [`NoProgress`] unless configured otherwise. With the `indicatif`
+/// feature, [`IndicatifReporter`] draws spinners and progress bars on the terminal, with the
+/// `tracing` feature, [`TracingReporter`] emits events instead.
+pub trait ProgressReporter: Send + Sync {
+    /// A log collection job was created for `resource`.
+    fn job_created(&self, _job_id: &str, _resource: &str) {}
+
+    /// An existing job is downloaded for `resource` instead of creating a new one.
+    fn job_reused(&self, _job_id: &str, _resource: &str) {}
+
+    /// Polling found a job in another state than before.
+    fn job_state_changed(&self, _job_id: &str, _state: JobState) {}
 
-pub struct SpinnerHelper;
-
-const TICK_TIME: u64 = 120;
-
-impl SpinnerHelper {
-    pub fn create(message: String) -> Result<ProgressBar, Error> {
-        let spinner = ProgressBar::new_spinner();
-        spinner.enable_steady_tick(Duration::from_millis(TICK_TIME));
-        spinner.set_style(
-            ProgressStyle::default_spinner()
-                // For more spinners check out the cli-spinners project:
-                // https://github.com/sindresorhus/cli-spinners/blob/master/spinners.json
-                .tick_strings(&[
-                    "□ □ □ □ □",
-                    "■ □ □ □ □",
-                    "□ ■ □ □ □",
-                    "□ □ ■ □ □",
-                    "□ □ □ ■ □",
-                    "□ □ □ □ ■",
-                    "■ ■ ■ ■ ■",
-                ])
-                .template("{spinner:.blue} {msg}")?,
-        );
-        spinner.set_message(message);
-
-        Ok(spinner)
-    }
+    /// A failed job is retried.
+    fn job_retried(&self, _job_id: &str) {}
+
+    /// Downloading the data of a job (re)started at `offset` bytes of `total_bytes`, if known.
+    fn download_started(&self, _job_id: &str, _offset: u64, _total_bytes: Option<u64>) {}
+
+    fn bytes_downloaded(&self, _job_id: &str, _bytes: u64) {}
+
+    /// The service is done with a job: its data was downloaded to the given path, or waiting for
+    /// or downloading it failed.
+    fn job_finished(&self, _job_id: &str, _result: Result<&Path, &Error>) {}
 }
+
+/// Reports nothing.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoProgress;
+
+impl ProgressReporter for NoProgress {}
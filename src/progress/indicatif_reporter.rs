@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use indicatif::MultiProgress;
+use indicatif::ProgressBar;
+use indicatif::ProgressStyle;
+
+use crate::error::Error;
+use crate::model::JobState;
+use crate::progress::ProgressReporter;
+
+pub struct SpinnerHelper;
+
+const TICK_TIME: u64 = 120;
+
+impl SpinnerHelper {
+    pub fn create(message: String) -> Result<ProgressBar, Error> {
+        let spinner = ProgressBar::new_spinner();
+        spinner.enable_steady_tick(Duration::from_millis(TICK_TIME));
+        spinner.set_style(
+            ProgressStyle::default_spinner()
+                // For more spinners check out the cli-spinners project:
+                // https://github.com/sindresorhus/cli-spinners/blob/master/spinners.json
+                .tick_strings(&[
+                    "□ □ □ □ □",
+                    "■ □ □ □ □",
+                    "□ ■ □ □ □",
+                    "□ □ ■ □ □",
+                    "□ □ □ ■ □",
+                    "□ □ □ □ ■",
+                    "■ ■ ■ ■ ■",
+                ])
+                .template("{spinner:.blue} {msg}")?,
+        );
+        spinner.set_message(message);
+
+        Ok(spinner)
+    }
+}
+
+pub struct DownloadBarHelper;
+
+impl DownloadBarHelper {
+    /// Creates a progress bar counting downloaded bytes. Without a known `content_length` it
+    /// falls back to a spinner that shows the bytes downloaded so far.
+    pub fn create(message: String, content_length: Option<u64>) -> Result<ProgressBar, Error> {
+        let bar = match content_length {
+            Some(length) => ProgressBar::new(length).with_style(
+                ProgressStyle::default_bar()
+                    .template(
+                        "{spinner:.blue} {msg} [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+                    )?
+                    .progress_chars("■■□"),
+            ),
+            None => ProgressBar::new_spinner().with_style(
+                ProgressStyle::default_spinner()
+                    .template("{spinner:.blue} {msg} {bytes} ({bytes_per_sec})")?,
+            ),
+        };
+        bar.enable_steady_tick(Duration::from_millis(TICK_TIME));
+        bar.set_message(message);
+
+        Ok(bar)
+    }
+}
+
+/// Draws a spinner per job while it is polled and a progress bar while its data is downloaded.
+#[derive(Default)]
+pub struct IndicatifReporter {
+    progress: MultiProgress,
+    bars: Mutex<HashMap<String, ProgressBar>>,
+}
+
+impl IndicatifReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn println(&self, message: String) {
+        // A message that cannot be drawn is not worth failing a download for.
+        let _ = self.progress.println(message);
+    }
+
+    fn bars(&self) -> std::sync::MutexGuard<'_, HashMap<String, ProgressBar>> {
+        self.bars
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The spinner of a job, created once its state is first reported.
+    fn spinner(&self, job_id: &str) -> ProgressBar {
+        self.bars()
+            .entry(job_id.to_string())
+            .or_insert_with(|| {
+                let spinner =
+                    SpinnerHelper::create(format!("Check job status of job with id: {job_id}"))
+                        .unwrap_or_else(|_| ProgressBar::hidden());
+                self.progress.add(spinner)
+            })
+            .clone()
+    }
+
+    fn remove(&self, job_id: &str) -> Option<ProgressBar> {
+        self.bars().remove(job_id)
+    }
+}
+
+impl ProgressReporter for IndicatifReporter {
+    fn job_created(&self, job_id: &str, resource: &str) {
+        self.println(format!("Started FTDC data job {job_id} for {resource}"));
+    }
+
+    fn job_reused(&self, job_id: &str, resource: &str) {
+        self.println(format!("Reusing FTDC data job {job_id} for {resource}"));
+    }
+
+    fn job_state_changed(&self, job_id: &str, state: JobState) {
+        let spinner = self.spinner(job_id);
+        match state {
+            JobState::InProgress => {
+                spinner.set_message(format!("IN_PROGRESS – job id: {job_id}"));
+                return;
+            }
+            JobState::Succcess | JobState::MarkedForExpiry => spinner.finish_with_message(format!(
+                "SUCCESS – FTDC data for job with id {job_id} will be downloaded."
+            )),
+            JobState::Failure => spinner.abandon_with_message(format!(
+                "FAILURE – Something went wrong creating job with id {job_id}."
+            )),
+            JobState::Expired => spinner.abandon_with_message(format!(
+                "EXPIRED – The data of job with id {job_id} is not available anymore."
+            )),
+        }
+        self.remove(job_id);
+    }
+
+    fn job_retried(&self, job_id: &str) {
+        self.println(format!("Retrying failed FTDC data job {job_id}"));
+    }
+
+    fn download_started(&self, job_id: &str, offset: u64, total_bytes: Option<u64>) {
+        let bar = DownloadBarHelper::create(
+            format!("PROGRESS – Download FTDC data for job with id: {job_id}"),
+            total_bytes,
+        )
+        .unwrap_or_else(|_| ProgressBar::hidden());
+        let bar = self.progress.add(bar);
+        bar.set_position(offset);
+
+        if let Some(previous) = self.bars().insert(job_id.to_string(), bar) {
+            previous.finish_and_clear();
+        }
+    }
+
+    fn bytes_downloaded(&self, job_id: &str, bytes: u64) {
+        if let Some(bar) = self.bars().get(job_id) {
+            bar.inc(bytes);
+        }
+    }
+
+    fn job_finished(&self, job_id: &str, result: Result<&Path, &Error>) {
+        let Some(bar) = self.remove(job_id) else {
+            return;
+        };
+        match result {
+            Ok(_) => bar.finish_with_message(format!(
+                "SUCCESS – FTDC data for job with id {job_id} downloaded."
+            )),
+            Err(error) => {
+                bar.abandon_with_message(format!("FAILURE – Job with id {job_id}: {error}"))
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use tracing::debug;
+use tracing::info;
+use tracing::trace;
+use tracing::warn;
+
+use crate::error::Error;
+use crate::model::JobState;
+use crate::progress::ProgressReporter;
+
+/// Emits the progress of jobs as `tracing` events, for services that log instead of drawing on
+/// a terminal.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TracingReporter;
+
+impl ProgressReporter for TracingReporter {
+    fn job_created(&self, job_id: &str, resource: &str) {
+        info!(job_id, resource, "Created log collection job");
+    }
+
+    fn job_reused(&self, job_id: &str, resource: &str) {
+        info!(job_id, resource, "Reusing log collection job");
+    }
+
+    fn job_state_changed(&self, job_id: &str, state: JobState) {
+        info!(
+            job_id,
+            state = state.as_str(),
+            "Log collection job state changed"
+        );
+    }
+
+    fn job_retried(&self, job_id: &str) {
+        warn!(job_id, "Retrying failed log collection job");
+    }
+
+    fn download_started(&self, job_id: &str, offset: u64, total_bytes: Option<u64>) {
+        debug!(
+            job_id,
+            offset, total_bytes, "Downloading log collection job"
+        );
+    }
+
+    fn bytes_downloaded(&self, job_id: &str, bytes: u64) {
+        trace!(job_id, bytes, "Downloaded bytes");
+    }
+
+    fn job_finished(&self, job_id: &str, result: Result<&Path, &Error>) {
+        match result {
+            Ok(path) => info!(job_id, path = %path.display(), "Downloaded log collection job"),
+            Err(error) => warn!(job_id, %error, "Log collection job failed"),
+        }
+    }
+}
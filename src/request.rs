@@ -0,0 +1,215 @@
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use crate::auth::AtlasAuth;
+
+/// The byte size of a job if none is given, enough for a couple of hours of FTDC data.
+pub const DEFAULT_BYTE_SIZE: u64 = 10_000_000;
+
+/// Defines a newtype around a `String` so that values of different meaning cannot be mixed up.
+/// The value of a `secret` is left out of its `Debug` output.
+macro_rules! string_newtype {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $name(String);
+
+        string_newtype!(@impl $name);
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+    };
+    ($(#[$meta:meta])* secret $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, PartialEq, Eq)]
+        pub struct $name(String);
+
+        string_newtype!(@impl $name);
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str(concat!(stringify!($name), "(***)"))
+            }
+        }
+    };
+    (@impl $name:ident) => {
+        impl $name {
+            pub fn new(value: impl Into<String>) -> Self {
+                Self(value.into())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+    };
+}
+
+string_newtype!(
+    /// The id of an Atlas project, also called group key.
+    GroupId
+);
+string_newtype!(
+    /// The name of a replica set, e.g. `atlas-<something>-shard-0`, or of a shard, e.g.
+    /// `some-name-shard-00`.
+    ReplicaSetName
+);
+string_newtype!(
+    /// The public key of an Atlas API key.
+    PublicKey
+);
+string_newtype!(
+    /// The private key of an Atlas API key.
+    secret PrivateKey
+);
+string_newtype!(
+    /// The client id of an Atlas service account.
+    ClientId
+);
+string_newtype!(
+    /// The client secret of an Atlas service account.
+    secret ClientSecret
+);
+
+/// The maximum number of bytes a log collection job collects per file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteSize(u64);
+
+impl ByteSize {
+    pub fn new(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for ByteSize {
+    fn default() -> Self {
+        Self(DEFAULT_BYTE_SIZE)
+    }
+}
+
+/// What a request against the Atlas Admin API is authenticated with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Credentials {
+    ApiKey {
+        public: PublicKey,
+        private: PrivateKey,
+    },
+    ServiceAccount {
+        client_id: ClientId,
+        client_secret: ClientSecret,
+    },
+}
+
+impl Credentials {
+    pub fn api_key(public: PublicKey, private: PrivateKey) -> Self {
+        Credentials::ApiKey { public, private }
+    }
+
+    pub fn service_account(client_id: ClientId, client_secret: ClientSecret) -> Self {
+        Credentials::ServiceAccount { client_id, client_secret }
+    }
+}
+
+impl From<&Credentials> for AtlasAuth {
+    fn from(credentials: &Credentials) -> Self {
+        match credentials {
+            Credentials::ApiKey { public, private } => {
+                AtlasAuth::digest(public.as_str(), private.as_str())
+            }
+            Credentials::ServiceAccount { client_id, client_secret } => {
+                AtlasAuth::service_account(client_id.as_str(), client_secret.as_str())
+            }
+        }
+    }
+}
+
+/// The FTDC data of a replica set to download.
+///
+/// Without credentials, the request is authenticated with the `AtlasAuth` the service was built
+/// with. Credentials of the request are turned into a new `AtlasAuth` per request, so service
+/// account tokens are only cached if the service holds the credentials.
+#[derive(Debug, Clone)]
+pub struct FtdcRequest {
+    pub group: GroupId,
+    pub replica_set: ReplicaSetName,
+    pub byte_size: ByteSize,
+    pub credentials: Option<Credentials>,
+}
+
+impl FtdcRequest {
+    pub fn new(group: GroupId, replica_set: ReplicaSetName) -> Self {
+        Self { group, replica_set, byte_size: ByteSize::default(), credentials: None }
+    }
+
+    pub fn with_byte_size(self, byte_size: ByteSize) -> Self {
+        Self { byte_size, ..self }
+    }
+
+    pub fn with_credentials(self, credentials: Credentials) -> Self {
+        Self { credentials: Some(credentials), ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::request::ByteSize;
+    use crate::request::ClientId;
+    use crate::request::ClientSecret;
+    use crate::request::Credentials;
+    use crate::request::FtdcRequest;
+    use crate::request::GroupId;
+    use crate::request::PrivateKey;
+    use crate::request::PublicKey;
+    use crate::request::ReplicaSetName;
+
+    #[test]
+    fn given_request_with_credentials_when_debug_then_leave_out_the_secrets() {
+        // Given
+        let request = FtdcRequest::new(
+            GroupId::new("my-group-key"),
+            ReplicaSetName::new("my-replica-set"),
+        );
+        let api_key = request.clone().with_credentials(Credentials::api_key(
+            PublicKey::new("my-public-key"),
+            PrivateKey::new("my-private-key"),
+        ));
+        let service_account = request.with_credentials(Credentials::service_account(
+            ClientId::new("my-client-id"),
+            ClientSecret::new("my-client-secret"),
+        ));
+
+        // When
+        let api_key = format!("{api_key:?}");
+        let service_account = format!("{service_account:?}");
+
+        // Then
+        assert!(api_key.contains("my-public-key") && api_key.contains("PrivateKey(***)"));
+        assert!(!api_key.contains("my-private-key"));
+        assert!(service_account.contains("my-client-id"));
+        assert!(!service_account.contains("my-client-secret"));
+    }
+
+    #[test]
+    fn given_no_byte_size_when_new_request_then_use_default_byte_size() {
+        // Given
+        let group = GroupId::new("my-group-key");
+        let replica_set = ReplicaSetName::new("my-replica-set");
+
+        // When
+        let request = FtdcRequest::new(group, replica_set);
+        let sized = request.clone().with_byte_size(ByteSize::new(42));
+
+        // Then
+        assert_eq!(request.byte_size.as_u64(), 10_000_000);
+        assert_eq!(sized.byte_size, ByteSize::new(42));
+        assert_eq!(request.credentials, None);
+    }
+}
@@ -0,0 +1,164 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use reqwest::header::RETRY_AFTER;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 4;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How requests against the Atlas Admin API are retried when Atlas rate limits them (`429 Too
+/// Many Requests`) or fails temporarily (`5xx`). A request is attempted at most `max_attempts`
+/// times.
+///
+/// The wait before the next attempt is the `Retry-After` header of the response if Atlas sends
+/// one, but at most `max_backoff`. Otherwise it starts at `initial_backoff`, doubles with every
+/// attempt up to `max_backoff` and is jittered so that concurrent requests do not retry in
+/// lockstep.
+///
+/// Requests that are not idempotent, like creating a job, are only retried on `429`: Atlas did
+/// not process them then, whereas after a `5xx` the job might have been created anyway.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn with_max_attempts(self, max_attempts: u32) -> Self {
+        Self { max_attempts, ..self }
+    }
+
+    pub fn with_initial_backoff(self, initial_backoff: Duration) -> Self {
+        Self { initial_backoff, ..self }
+    }
+
+    pub fn with_max_backoff(self, max_backoff: Duration) -> Self {
+        Self { max_backoff, ..self }
+    }
+
+    pub(crate) fn should_retry(&self, status: StatusCode, idempotent: bool) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || (idempotent && status.is_server_error())
+    }
+
+    /// The wait before the attempt following attempt number `attempt` (starting at `1`).
+    pub(crate) fn backoff(&self, attempt: u32, headers: &HeaderMap) -> Duration {
+        if let Some(retry_after) = retry_after(headers) {
+            return retry_after.min(self.max_backoff.max(self.initial_backoff));
+        }
+        self.exponential_backoff(attempt)
+    }
+
+    /// The wait before the attempt following attempt number `attempt` (starting at `1`) when
+    /// there is no response to take a `Retry-After` from, e.g. after a dropped connection.
+    pub(crate) fn exponential_backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff.max(self.initial_backoff));
+        // Waits at least half of the backoff, the other half is random.
+        let half = backoff / 2;
+        half + half.mul_f64(fastrand::f64())
+    }
+}
+
+/// `Retry-After` in seconds. Atlas does not send the HTTP date form.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::StatusCode;
+    use reqwest::header::HeaderMap;
+    use reqwest::header::HeaderValue;
+    use reqwest::header::RETRY_AFTER;
+
+    use crate::retry::RetryConfig;
+
+    #[test]
+    fn given_attempts_when_backoff_then_double_up_to_max_backoff_with_jitter() {
+        // Given
+        let retry_config = RetryConfig::default()
+            .with_initial_backoff(Duration::from_secs(2))
+            .with_max_backoff(Duration::from_secs(5));
+        let headers = HeaderMap::new();
+
+        // When
+        let first = retry_config.backoff(1, &headers);
+        let second = retry_config.backoff(2, &headers);
+        let third = retry_config.backoff(3, &headers);
+
+        // Then
+        assert!((Duration::from_secs(1)..=Duration::from_secs(2)).contains(&first));
+        assert!((Duration::from_secs(2)..=Duration::from_secs(4)).contains(&second));
+        assert!((Duration::from_millis(2_500)..=Duration::from_secs(5)).contains(&third));
+    }
+
+    #[test]
+    fn given_retry_after_header_when_backoff_then_wait_as_long_as_atlas_asks() {
+        // Given
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("20"));
+
+        // When
+        let backoff = RetryConfig::default().backoff(1, &headers);
+
+        // Then
+        assert_eq!(backoff, Duration::from_secs(20));
+    }
+
+    #[test]
+    fn given_retry_after_header_beyond_max_backoff_when_backoff_then_wait_max_backoff() {
+        // Given
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("86400"));
+
+        // When
+        let backoff = RetryConfig::default()
+            .with_max_backoff(Duration::from_secs(60))
+            .backoff(1, &headers);
+
+        // Then
+        assert_eq!(backoff, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn given_non_idempotent_request_when_should_retry_then_only_on_rate_limit() {
+        // Given
+        let retry_config = RetryConfig::default();
+
+        // When
+        let rate_limited = retry_config.should_retry(StatusCode::TOO_MANY_REQUESTS, false);
+        let server_error = retry_config.should_retry(StatusCode::SERVICE_UNAVAILABLE, false);
+        let idempotent_server_error = retry_config.should_retry(StatusCode::BAD_GATEWAY, true);
+        let client_error = retry_config.should_retry(StatusCode::NOT_FOUND, true);
+
+        // Then
+        assert!(rate_limited);
+        assert!(!server_error);
+        assert!(idempotent_server_error);
+        assert!(!client_error);
+    }
+}